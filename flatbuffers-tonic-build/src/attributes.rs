@@ -0,0 +1,112 @@
+// Generates constants with the schema attributes and descriptor of each service and method,
+// for middleware.
+
+use flatbuffers_util::reflect::Service;
+use heck::ToShoutySnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::ident::{check_collisions, grpc_package, grpc_path, tonic_snake_case};
+
+pub(crate) fn compile_attributes(
+    services: &[Service],
+    default_package: &str,
+) -> Result<TokenStream, String> {
+    let mut content = TokenStream::new();
+    for service in services {
+        content.extend(compile_service_attributes(service, default_package)?);
+    }
+    Ok(content)
+}
+
+fn attribute_list<'a>(
+    attributes: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> TokenStream {
+    let entries = attributes.into_iter().map(|(k, v)| quote! { (#k, #v) });
+    quote! { &[#(#entries),*] }
+}
+
+fn compile_service_attributes(
+    service: &Service,
+    default_package: &str,
+) -> Result<TokenStream, String> {
+    check_collisions(
+        &format!("attribute constants of service {}", service.name),
+        [
+            ("service", "SERVICE".to_string()),
+            ("descriptor", "DESCRIPTOR".to_string()),
+        ]
+        .into_iter()
+        .chain(
+            service
+                .methods
+                .iter()
+                .map(|m| (m.name.as_str(), m.name.to_shouty_snake_case())),
+        ),
+    )?;
+    let attributes_mod = format_ident!("{}_attributes", tonic_snake_case(&service.name));
+    let service_attributes = attribute_list(&service.attributes);
+    let doc = format!(
+        " Schema attributes and descriptor of the `{}` service and its methods.",
+        service.name
+    );
+
+    let mut consts = Vec::new();
+    let mut arms = Vec::new();
+    let mut methods = Vec::new();
+    for method in &service.methods {
+        let name = format_ident!("{}", method.name.to_shouty_snake_case());
+        let method_doc = format!(" Attributes of `{}`.", method.name);
+        let attributes = attribute_list(&method.attributes);
+        consts.push(quote! {
+            #[doc = #method_doc]
+            pub const #name: &[(&str, &str)] = #attributes;
+        });
+        let path = grpc_path(service, &method.name, default_package);
+        arms.push(quote! { #path => Some(#name), });
+        let method_name = &method.name;
+        let request = &method.request_name;
+        let response = &method.response_name;
+        let client_streaming = method.client_streaming;
+        let server_streaming = method.server_streaming;
+        methods.push(quote! {
+            flatbuffers_tonic::MethodDescriptor {
+                name: #method_name,
+                path: #path,
+                request: #request,
+                response: #response,
+                client_streaming: #client_streaming,
+                server_streaming: #server_streaming,
+            }
+        });
+    }
+    let service_name = match grpc_package(service, default_package) {
+        "" => service.name.clone(),
+        package => format!("{package}.{}", service.name),
+    };
+
+    Ok(quote! {
+        #[doc = #doc]
+        pub mod #attributes_mod {
+            /// Attributes of the service.
+            pub const SERVICE: &[(&str, &str)] = #service_attributes;
+
+            #(#consts)*
+
+            /// Descriptor of the service, i.e. to route it with `flatbuffers_tonic::GrpcProxy`.
+            pub const DESCRIPTOR: flatbuffers_tonic::ServiceDescriptor =
+                flatbuffers_tonic::ServiceDescriptor {
+                    name: #service_name,
+                    methods: &[#(#methods),*],
+                };
+
+            /// Attributes of the method with the gRPC `path`, i.e. `InspectContext::path`.
+            pub fn method(path: &str) -> Option<&'static [(&'static str, &'static str)]> {
+                match path {
+                    #(#arms)*
+                    _ => None,
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,63 @@
+// Generates typed status details helpers for methods with an `error` attribute.
+
+use flatbuffers_util::reflect::Service;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::ident::{WrapperNames, method_ident, tonic_snake_case};
+
+pub(crate) fn compile_errors(services: &[Service], wrappers: &WrapperNames) -> TokenStream {
+    let mut content = TokenStream::new();
+    for service in services {
+        content.extend(compile_service_errors(service, wrappers));
+    }
+    content
+}
+
+fn compile_service_errors(service: &Service, wrappers: &WrapperNames) -> TokenStream {
+    let mut fns = Vec::new();
+    for method in &service.methods {
+        let (Some(error_type), Some(error_name)) = (method.error_type(), &method.error_name) else {
+            continue;
+        };
+        let method_name = method_ident(&method.name);
+        let name = format_ident!("{}", method_name);
+        let details_name = format_ident!("{}_details", method_name.trim_start_matches("r#"));
+        let error = format_ident!("{}", wrappers.get(service, error_name));
+        let doc = format!(
+            " Status for `{}` carrying `{}` details.",
+            method.name, error_type
+        );
+        let details_doc = format!(
+            " Decode the `{error_type}` details of a `{}` status.",
+            method.name
+        );
+        fns.push(quote! {
+            #[doc = #doc]
+            pub fn #name(
+                code: tonic::Code,
+                message: impl Into<String>,
+                details: super::#error,
+            ) -> tonic::Status {
+                flatbuffers_tonic::status_with_details(code, message, details)
+            }
+
+            #[doc = #details_doc]
+            pub fn #details_name(
+                status: &tonic::Status,
+            ) -> Option<Result<super::#error, flatbuffers::InvalidFlatbuffer>> {
+                flatbuffers_tonic::StatusExt::fb_details(status)
+            }
+        });
+    }
+    if fns.is_empty() {
+        return TokenStream::new();
+    }
+    let errors_mod = format_ident!("{}_error", tonic_snake_case(&service.name));
+    quote! {
+        /// Error details declared with the `error` attribute.
+        pub mod #errors_mod {
+            #(#fns)*
+        }
+    }
+}
@@ -2,24 +2,40 @@
 
 use std::path::Path;
 
-pub(crate) fn compile_flat_buffer_self<P>(fbs_path: &[P])
+use crate::Builder;
+
+pub(crate) fn compile_flat_buffer_self<P>(fbs_path: &[P], builder: &Builder)
 where
     P: AsRef<Path>,
 {
-    // Use OUT_DIR/flatbuffers/<file_stem> as output path
+    // Use OUT_DIR/flatbuffers/<file_stem> as output path, see `output_subdir`
     // flatc has a bug the multi file generation does not work correctly.
     // So we generate one by one.
     let output_path = std::env::var("OUT_DIR").unwrap();
+    let mut args = Vec::new();
+    for dir in &builder.include_paths {
+        args.push("-I".to_string());
+        args.push(dir.to_str().expect("include path is not utf8").to_string());
+    }
+    args.extend(builder.flatc_args.iter().cloned());
     for path in fbs_path {
-        // get the package name from the file name without extension
         let path = path.as_ref();
-        let file_stem = path.file_stem().unwrap().to_str().unwrap();
-        let output_path = Path::new(&output_path).join("flatbuffers").join(file_stem);
+        let output_path = Path::new(&output_path).join(output_subdir(path));
         // generate for each fbs file one by one
         use flatbuffers_build::BuilderOptions;
-        BuilderOptions::new_with_files([path])
+        let mut options = BuilderOptions::new_with_files([path])
             .set_output_path(&output_path)
-            .compile()
-            .expect("flatbuffer compilation failed");
+            .add_flatc_arguments(&args);
+        if builder.gen_object_api {
+            options = options.gen_object_api();
+        }
+        options.compile().expect("flatbuffer compilation failed");
     }
 }
+
+/// Where the flatc output of an fbs file goes, relative to OUT_DIR.
+/// Named after the file rather than its namespace, as several files can share a namespace.
+pub(crate) fn output_subdir(fbs_path: &Path) -> String {
+    let file_stem = fbs_path.file_stem().unwrap().to_str().unwrap();
+    format!("flatbuffers/{file_stem}")
+}
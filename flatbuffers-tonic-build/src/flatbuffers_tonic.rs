@@ -1,63 +1,358 @@
-use std::path::Path;
+use std::{
+    collections::{BTreeMap, BTreeSet, btree_map::Entry},
+    path::{Path, PathBuf},
+};
 
-use flatbuffers_util::reflect::{GeneratorContext, MessageType};
-use heck::ToSnakeCase;
+use flatbuffers_util::reflect::{GeneratorContext, MessageType, namespace_rs};
 use proc_macro2::TokenStream;
 use quote::quote;
 
-pub(crate) fn compile_flatbuffers_tonic_file_only(
+use crate::{
+    Builder, Generator,
+    ident::{
+        WrapperNames, check_collisions, flatc_type_ident, grpc_package, method_ident, wrapper_ident,
+    },
+};
+
+/// Check a fbs file against its baselines and parse its services.
+fn parse_fbs_file(
     path: &Path,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let schema = flatbuffers_util::reflect::compile_reflection_schema(path);
+    builder: &Builder,
+) -> Result<GeneratorContext, Box<dyn std::error::Error>> {
+    let schema = flatbuffers_util::reflect::compile_reflection_schema_with_includes(
+        path,
+        &builder.include_paths,
+    );
     let schema_ref = schema.get_ref();
-    let gen_ctx = flatbuffers_util::reflect::GeneratorContext::parse_from_schema(&schema_ref);
-    let types = gen_ctx.collect_in_out_types();
-    let services = gen_ctx.get_services();
+    if let Some(baseline) = &builder.compat_baseline {
+        check_compatibility(path, &schema_ref, baseline, &builder.include_paths)?;
+    }
+    if let Some(dir) = &builder.snapshot_dir {
+        check_snapshot(path, &schema_ref, dir, builder.locked_snapshots)?;
+    }
+    GeneratorContext::parse_from_schema(&schema_ref)
+        .map_err(|e| format!("{}: {e}", path.display()).into())
+}
+
+/// Generate the code of one namespace from the fbs files declaring it.
+fn compile_namespace(
+    gen_ctx: GeneratorContext,
+    files: &[&Path],
+    builder: &Builder,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Processing service: {:?}", gen_ctx.get_services());
+
+    let files = files
+        .iter()
+        .map(|f| f.to_str().expect("fbs path is not utf8").to_string())
+        .collect();
+    let generated = Generator::new(gen_ctx, files, builder.clone()).generate()?;
+    generated.write_to(std::env::var("OUT_DIR").unwrap())?;
+    Ok(())
+}
+
+/// Compare the schema with its previous version in the baseline dir.
+fn check_compatibility(
+    path: &Path,
+    schema: &flatbuffers_util::reflect::reflection::reflection::Schema,
+    baseline: &Path,
+    include_paths: &[PathBuf],
+) -> Result<(), String> {
+    let old_path = baseline.join(path.file_name().expect("fbs path has no file name"));
+    println!("cargo:rerun-if-changed={}", old_path.display());
+    if !old_path.exists() {
+        return Ok(());
+    }
+    let old_schema = flatbuffers_util::reflect::compile_reflection_schema_with_includes(
+        &old_path,
+        include_paths,
+    );
+    let changes =
+        flatbuffers_util::reflect::compat::check_compatibility(&old_schema.get_ref(), schema);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let changes: Vec<String> = changes.iter().map(|c| format!("  {c}")).collect();
+    Err(format!(
+        "{} breaks compatibility with {}:\n{}",
+        path.display(),
+        old_path.display(),
+        changes.join("\n")
+    ))
+}
+
+/// Env var that lets a build replace a snapshot with breaking changes.
+const ACCEPT_BREAKING_ENV: &str = "FLATBUFFERS_TONIC_ACCEPT_BREAKING";
 
-    println!("Processing service: {services:?}");
-    // println!("Processing types: {types:?}");
+/// Env var set by CI systems, which lock the snapshots by default.
+const CI_ENV: &str = "CI";
+
+/// Compare the schema with its snapshot in `dir`, then update the snapshot
+/// unless it is locked.
+fn check_snapshot(
+    path: &Path,
+    schema: &flatbuffers_util::reflect::reflection::reflection::Schema,
+    dir: &Path,
+    locked: Option<bool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use flatbuffers_util::reflect::compat::Snapshot;
 
-    // generate types alias file
-    let out_dir = std::env::var("OUT_DIR").unwrap();
-    let out_dir = Path::new(&out_dir);
-    let tonic_services = get_tonic_services(services);
-    tonic_prost_build::manual::Builder::new().compile(&tonic_services);
+    let stem = path.file_stem().expect("fbs path has no file name");
+    let snapshot_path = dir.join(format!("{}.lock", stem.to_string_lossy()));
+    println!("cargo:rerun-if-changed={}", snapshot_path.display());
+    println!("cargo:rerun-if-env-changed={ACCEPT_BREAKING_ENV}");
+    println!("cargo:rerun-if-env-changed={CI_ENV}");
+    let locked = locked.unwrap_or_else(|| {
+        std::env::var_os(CI_ENV).is_some_and(|v| !v.is_empty() && v != "0" && v != "false")
+    });
 
-    let content = compile_flatbuffers_tonic_file(&gen_ctx, &types);
-    write_tonic_flatbuffers_file(out_dir, &content, &gen_ctx.get_namespace())?;
+    let current = Snapshot::from_schema(schema);
+    let text = format!(
+        "# Wire surface of {}, checked by flatbuffers-tonic-build.\n{current}",
+        path.file_name().unwrap().to_string_lossy()
+    );
+    let old_text = if snapshot_path.exists() {
+        let old_text = std::fs::read_to_string(&snapshot_path)?;
+        if old_text == text {
+            return Ok(());
+        }
+        let old =
+            Snapshot::parse(&old_text).map_err(|e| format!("{}: {e}", snapshot_path.display()))?;
+        let changes = old.breaking_changes(&current);
+        let accepted = std::env::var_os(ACCEPT_BREAKING_ENV).is_some_and(|v| v != "0");
+        if !changes.is_empty() && !accepted {
+            let changes: Vec<String> = changes.iter().map(|c| format!("  {c}")).collect();
+            return Err(format!(
+                "{} breaks compatibility with {}:\n{}\nset {ACCEPT_BREAKING_ENV}=1 to accept the changes",
+                path.display(),
+                snapshot_path.display(),
+                changes.join("\n")
+            )
+            .into());
+        }
+        old_text
+    } else {
+        String::new()
+    };
+    if locked {
+        return Err(format!(
+            "{} is out of date with {}, update it with a build outside of CI or with \
+             `locked_snapshots(false)`:\n{}",
+            snapshot_path.display(),
+            path.display(),
+            line_diff(&old_text, &text)
+        )
+        .into());
+    }
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&snapshot_path, text)?;
     Ok(())
 }
 
-/// Compile multiple fbs files
+/// Lines only in `old` prefixed with `-`, and lines only in `new` with `+`.
+fn line_diff(old: &str, new: &str) -> String {
+    let old_lines: BTreeSet<&str> = old.lines().collect();
+    let new_lines: BTreeSet<&str> = new.lines().collect();
+    let removed = old
+        .lines()
+        .filter(|l| !new_lines.contains(l))
+        .map(|l| format!("  -{l}"));
+    let added = new
+        .lines()
+        .filter(|l| !old_lines.contains(l))
+        .map(|l| format!("  +{l}"));
+    removed.chain(added).collect::<Vec<_>>().join("\n")
+}
+
+/// Compile multiple fbs files, files of the same namespace share one generated file.
 pub(crate) fn compile_flatbuffers_tonic_file_list_only<P>(
     path_list: &[P],
+    builder: &Builder,
 ) -> Result<(), Box<dyn std::error::Error>>
 where
     P: AsRef<Path>,
 {
+    check_collisions(
+        "fbs files",
+        path_list.iter().map(|p| {
+            let path = p.as_ref();
+            (
+                path.to_str().expect("fbs path is not utf8"),
+                crate::flatbuffers_self::output_subdir(path),
+            )
+        }),
+    )?;
+    let mut namespaces: BTreeMap<String, (GeneratorContext, Vec<&Path>)> = BTreeMap::new();
     for path in path_list {
-        compile_flatbuffers_tonic_file_only(path.as_ref())?;
+        let path = path.as_ref();
+        let gen_ctx = parse_fbs_file(path, builder)?;
+        match namespaces.entry(package_name(&gen_ctx, builder)) {
+            Entry::Vacant(entry) => {
+                entry.insert((gen_ctx, vec![path]));
+            }
+            Entry::Occupied(mut entry) => {
+                let (merged, files) = entry.get_mut();
+                if merged.get_namespace() != gen_ctx.get_namespace() {
+                    return Err(format!(
+                        "{}: the default package `{}` of files without namespace is also a namespace",
+                        path.display(),
+                        entry.key()
+                    )
+                    .into());
+                }
+                merged.merge(gen_ctx);
+                files.push(path);
+            }
+        }
+    }
+    for name in &builder.wrapped_tables {
+        let namespace = table_namespace(name);
+        if !namespaces
+            .values()
+            .any(|(gen_ctx, _)| gen_ctx.get_namespace() == namespace)
+        {
+            return Err(
+                format!("wrapped table `{name}` is not in a namespace of the fbs files").into(),
+            );
+        }
+    }
+    for (gen_ctx, files) in namespaces.into_values() {
+        compile_namespace(gen_ctx, &files, builder)?;
     }
     Ok(())
 }
 
-fn get_tonic_services(
+/// Tables to generate `Owned*` wrappers for: the rpc inputs and outputs,
+/// then the tables of the namespace selected by the builder.
+pub(crate) fn owned_types(
+    gen_ctx: &GeneratorContext,
+    builder: &Builder,
+    files: &[String],
+) -> Result<Vec<MessageType>, String> {
+    // flatc checks the request and response types, not the `error` attribute.
+    for service in gen_ctx.get_services() {
+        for method in &service.methods {
+            let Some(error_name) = &method.error_name else {
+                continue;
+            };
+            let is_table = gen_ctx
+                .object(&service.qualified_type(error_name))
+                .is_some_and(|object| !object.is_struct);
+            if !is_table {
+                return Err(format!(
+                    "rpc {}.{}: error type {error_name} is not a table in the schema",
+                    service.name, method.name
+                ));
+            }
+        }
+    }
+    let mut types = gen_ctx.collect_in_out_types();
+    let namespace = gen_ctx.get_namespace();
+    if gen_ctx.services.is_empty() {
+        let namespaces = gen_ctx.declared_namespaces();
+        if namespaces.len() > 1 {
+            let namespaces: Vec<_> = namespaces.into_iter().collect();
+            return Err(format!(
+                "{}: without rpc_service the file needs one namespace, it declares types in {}",
+                files.join(", "),
+                namespaces.join(", ")
+            ));
+        }
+    }
+    for name in &builder.wrapped_tables {
+        if table_namespace(name) != namespace {
+            continue;
+        }
+        match gen_ctx.object(name) {
+            Some(object) if object.is_struct => {
+                return Err(format!(
+                    "wrapped table `{name}` is a struct, only tables have owned wrappers"
+                ));
+            }
+            Some(_) => {}
+            None => return Err(format!("wrapped table `{name}` is not in the schema")),
+        }
+    }
+    // Types of included files are only generated with `--gen-all`.
+    let file_names: Vec<&str> = files
+        .iter()
+        .filter_map(|f| Path::new(f).file_name()?.to_str())
+        .collect();
+    for object in &gen_ctx.objects {
+        if object.is_struct || object.namespace().unwrap_or_default() != namespace {
+            continue;
+        }
+        let declared = object
+            .declaration_file
+            .as_deref()
+            .is_some_and(|f| file_names.contains(&f));
+        let wrapped =
+            (builder.wrap_all_tables && declared) || builder.wrapped_tables.contains(&object.name);
+        if wrapped && !types.iter().any(|t| t.full_name == object.name) {
+            types.push(MessageType::new(&object.name));
+        }
+    }
+    Ok(types)
+}
+
+/// Namespace of a fully qualified table name, empty without one.
+fn table_namespace(name: &str) -> &str {
+    name.rsplit_once('.').map_or("", |(namespace, _)| namespace)
+}
+
+pub(crate) const CLIENT_CODEC: &str = "flatbuffers_tonic::FlatBuffersCodec";
+pub(crate) const SERVER_CODEC: &str = "flatbuffers_tonic::FlatBuffersServerCodec";
+
+fn default_package(builder: &Builder) -> &str {
+    builder.default_package.as_deref().unwrap_or("")
+}
+
+/// Package a file generates for: its namespace, or the default package.
+/// Names the generated `flatbuffers_tonic.<package>.rs`.
+pub(crate) fn package_name(gen_ctx: &GeneratorContext, builder: &Builder) -> String {
+    match gen_ctx.get_namespace() {
+        namespace if namespace.is_empty() => builder.default_package.clone().unwrap_or_default(),
+        namespace => namespace,
+    }
+}
+
+pub(crate) fn get_tonic_services(
     services: &[flatbuffers_util::reflect::Service],
-) -> Vec<tonic_prost_build::manual::Service> {
+    codec_path: &str,
+    builder: &Builder,
+    wrappers: &WrapperNames,
+) -> Result<Vec<tonic_prost_build::manual::Service>, String> {
     let mut tonic_services = Vec::new();
     for service in services {
-        let namespace = service.namespace.as_ref().expect("no namespace found");
+        check_collisions(
+            &format!("methods of service {}", service.name),
+            service
+                .methods
+                .iter()
+                .map(|m| (m.name.as_str(), method_ident(&m.name))),
+        )?;
         let mut tonic_service = tonic_prost_build::manual::Service::builder()
-            .package(namespace)
+            .package(grpc_package(service, default_package(builder)))
             .name(&service.name);
+        for line in &service.documentation {
+            tonic_service = tonic_service.comment(line);
+        }
         for method in &service.methods {
-            let method_name = get_method_name(method);
             let mut tonic_method = tonic_prost_build::manual::Method::builder()
-                .name(&method_name)
+                .name(method_ident(&method.name))
                 .route_name(&method.name)
-                .input_type(format!("super::Owned{}", method.request_type()))
-                .output_type(format!("super::Owned{}", method.response_type()))
-                .codec_path("flatbuffers_tonic::FlatBuffersCodec");
+                .input_type(format!(
+                    "super::{}",
+                    wrappers.get(service, &method.request_name)
+                ))
+                .output_type(format!(
+                    "super::{}",
+                    wrappers.get(service, &method.response_name)
+                ))
+                .codec_path(codec_path);
+            for line in &method.documentation {
+                tonic_method = tonic_method.comment(line);
+            }
             if method.client_streaming {
                 tonic_method = tonic_method.client_streaming();
             }
@@ -68,106 +363,302 @@ fn get_tonic_services(
         }
         tonic_services.push(tonic_service.build());
     }
-    tonic_services
+    Ok(tonic_services)
 }
 
-fn get_method_name(method: &flatbuffers_util::reflect::Method) -> String {
-    // remove the prefix if any, and convert to snake_case
-    let name = method.name.rsplit('.').next().unwrap().to_string();
-    name.to_snake_case()
-}
-
-pub fn compile_types_alias(package: &str, types: &Vec<MessageType>) -> TokenStream {
+pub(crate) fn compile_types_alias(
+    gen_ctx: &GeneratorContext,
+    types: &Vec<MessageType>,
+    wrappers: &WrapperNames,
+    object_api: bool,
+) -> TokenStream {
     let mut content = TokenStream::new();
 
     for t in types {
-        let wrapper_type = quote::format_ident!("Owned{}", t.fb_type);
-        let rs_type = quote::format_ident!("{}", t.fb_type);
-        let rs_type_mod: syn::Path = syn::parse_str(package).unwrap();
-        // add definition
+        let wrapper = wrappers.of(&t.full_name);
+        // Wrappers with the namespace in their name document the full table name.
+        let table = if wrapper == wrapper_ident(&t.fb_type) {
+            &t.fb_type
+        } else {
+            &t.full_name
+        };
+        let docs = gen_ctx.documentation(t);
+        let docs = if docs.is_empty() {
+            vec![format!(" Owned `{table}` table.")]
+        } else {
+            docs.to_vec()
+        };
+        let wrapper_type = quote::format_ident!("{}", wrapper);
+        let rs_type = quote::format_ident!("{}", flatc_type_ident(&t.fb_type));
+        let rs_type_mod = flatc_type_mod(t);
         content.extend(quote! {
-            pub struct #wrapper_type(pub flatbuffers_tonic::OwnedFB<#rs_type_mod::#rs_type<'static>>);
-            impl #wrapper_type {
-                pub fn get_ref<'a>(&'a self) -> #rs_type_mod::#rs_type<'a> {
-                    self.0.get_ref()
-                }
-            }
-
-            /// Conversion from OwnedFB to wrapper type
-            impl From<flatbuffers_tonic::OwnedFB<#rs_type_mod::#rs_type<'static>>> for #wrapper_type {
-                fn from(value: flatbuffers_tonic::OwnedFB<#rs_type_mod::#rs_type<'static>>) -> Self {
-                    Self(value)
-                }
+            flatbuffers_tonic::owned_wrapper! {
+                #(#[doc = #docs])*
+                pub #wrapper_type(#rs_type_mod #rs_type)
             }
         });
-        // add impl
-        content.extend(quote! {
-            impl flatbuffers_tonic::OwnedFBCodecable for #wrapper_type {
-                fn new_from_bytes(bytes: bytes::Bytes) -> Result<Self, flatbuffers::InvalidFlatbuffer> {
-                    let owned = flatbuffers_tonic::OwnedFB::<#rs_type_mod::#rs_type<'static>>::new_from_bytes(bytes)?;
-                    Ok(Self(owned))
+        if object_api {
+            // flatc names the object API type `<table>T`.
+            let object_type = quote::format_ident!("{}T", t.fb_type);
+            content.extend(quote! {
+                impl #wrapper_type {
+                    /// Copy into the object API type.
+                    pub fn unpack(&self) -> #rs_type_mod #object_type {
+                        self.get_ref().unpack()
+                    }
                 }
 
-                fn into_bytes(self) -> bytes::Bytes {
-                    self.0.into_bytes()
+                impl From<&#rs_type_mod #object_type> for #wrapper_type {
+                    fn from(value: &#rs_type_mod #object_type) -> Self {
+                        let mut builder = flatbuffers_tonic::FBBuilder::new();
+                        let root = value.pack(builder.get_mut());
+                        Self(builder.finish_owned(root))
+                    }
                 }
-            }
+            });
+        }
+    }
+    content
+}
+
+/// Module path of the flatc type of a table, without a namespace flatc
+/// generates the types at the root.
+fn flatc_type_mod(t: &MessageType) -> TokenStream {
+    match t.namespace.as_deref().map(namespace_rs) {
+        Some(namespace) => {
+            let path: syn::Path = syn::parse_str(&namespace).unwrap();
+            quote! { #path:: }
+        }
+        None => TokenStream::new(),
+    }
+}
+
+/// `TableRef` of the wrapped tables and the tables the files declare in the package,
+/// the children `OwnedFB::project` takes.
+fn compile_table_refs(
+    gen_ctx: &GeneratorContext,
+    types: &[MessageType],
+    files: &[String],
+) -> TokenStream {
+    let namespace = gen_ctx.get_namespace();
+    let file_names: Vec<&str> = files
+        .iter()
+        .filter_map(|f| Path::new(f).file_name()?.to_str())
+        .collect();
+    let declared = gen_ctx.objects.iter().filter(|object| {
+        !object.is_struct
+            && object.namespace().unwrap_or_default() == namespace
+            && object
+                .declaration_file
+                .as_deref()
+                .is_some_and(|f| file_names.contains(&f))
+            && !types.iter().any(|t| t.full_name == object.name)
+    });
+    let tables = types
+        .iter()
+        .cloned()
+        .chain(declared.map(|object| MessageType::new(&object.name)));
+    let mut content = TokenStream::new();
+    for t in tables {
+        let rs_type_mod = flatc_type_mod(&t);
+        let rs_type = quote::format_ident!("{}", flatc_type_ident(&t.fb_type));
+        content.extend(quote! {
+            flatbuffers_tonic::impl_table_ref!(#rs_type_mod #rs_type);
         });
     }
     content
 }
 
-pub fn compile_flatbuffers_tonic_file(
+pub(crate) fn compile_flatbuffers_tonic_file(
     gen_ctx: &GeneratorContext,
     types: &Vec<MessageType>,
-) -> TokenStream {
+    wrappers: &WrapperNames,
+    builder: &Builder,
+    files: &[String],
+) -> Result<TokenStream, String> {
     let services = gen_ctx.get_services();
     let package_rs = gen_ctx.get_namespace_rs();
-    let package = gen_ctx.get_namespace();
+    let package = package_name(gen_ctx, builder);
     let mut content = TokenStream::new();
 
-    let types_content = compile_types_alias(&package_rs, types);
+    let types_content = compile_types_alias(gen_ctx, types, wrappers, builder.gen_object_api);
 
-    content.extend(quote! {
-        /// This file is generated by flatbuffers-tonic-build
-        /// Do not edit this file manually
-    });
+    // Included code is wrapped in private modules so its lints can be scoped,
+    // the module names are per package so several files can share a parent module.
+    let package_ident = match namespace_rs(&package) {
+        package if package.is_empty() => "root".to_string(),
+        package => package.replace("::", "_"),
+    };
+    let flatbuffers_mod = quote::format_ident!("flatbuffers_{}", package_ident);
+    let grpc_mod = quote::format_ident!("grpc_{}", package_ident);
 
-    // add flatbuffers includes
+    // add flatbuffers includes, flatc output is per file,
+    // each in a private module whose namespace module is re-exported below.
+    // Includes are relative to this file, so the output can be moved as a whole.
+    let mut file_mods = Vec::new();
+    let mut includes = Vec::new();
+    for file in files {
+        let subdir = crate::flatbuffers_self::output_subdir(Path::new(file));
+        let file_mod = quote::format_ident!("file_{}", file_ident(file));
+        let mod_rs = format!("{subdir}/mod.rs");
+        includes.push(quote! {
+            mod #file_mod {
+                include!(#mod_rs);
+            }
+        });
+        file_mods.push(file_mod);
+    }
+    check_collisions(
+        &format!("files of package {package}"),
+        files.iter().map(|f| (f.as_str(), file_ident(f))),
+    )
+    .map_err(|e| e.to_string())?;
+    // The namespace of the package is re-exported from every file, other namespaces
+    // from the files declaring wrapped tables in them, i.e. `v1.Request` of a `v2` service.
+    let mut tree = NamespaceTree::default();
+    for file_mod in &file_mods {
+        tree.insert(&package_rs, file_mod);
+    }
+    let package_namespace = gen_ctx.get_namespace();
+    for t in types {
+        let Some(namespace) = t.namespace.as_deref().filter(|ns| *ns != package_namespace) else {
+            continue;
+        };
+        let declaration_file = gen_ctx
+            .object(&t.full_name)
+            .and_then(|object| object.declaration_file.as_deref());
+        for (file, file_mod) in files.iter().zip(&file_mods) {
+            if Path::new(file).file_name().and_then(|f| f.to_str()) == declaration_file {
+                tree.insert(&namespace_rs(namespace), file_mod);
+            }
+        }
+    }
+    let tree = tree.to_tokens(&[]);
     content.extend(quote! {
-        // flatbuffers generated code has warnings.
-        include!(concat!(env!("OUT_DIR"), "/flatbuffers/", #package, "/mod.rs"));
+        // flatc generated code has warnings.
+        #[allow(
+            unknown_lints,
+            unused_imports,
+            dead_code,
+            non_camel_case_types,
+            non_snake_case,
+            non_upper_case_globals,
+            unsafe_op_in_unsafe_fn,
+            mismatched_lifetime_syntaxes,
+            clippy::all
+        )]
+        mod #flatbuffers_mod {
+            #(#includes)*
+            #tree
+        }
+        pub use #flatbuffers_mod::*;
     });
 
-    // add include for flatbuffers and tonic files
-    for service in services {
-        let namespace = service.namespace.as_ref().expect("no namespace found");
-        let service_name = &service.name;
+    // add include for tonic files, a schema without services only has wrappers
+    let tonic_includes = services.iter().map(|service| {
+        let file_name = service_file_name(
+            grpc_package(service, default_package(builder)),
+            &service.name,
+        );
+        let client = format!("client/{file_name}");
+        let server = format!("server/{file_name}");
+        quote! {
+            include!(#client);
+            include!(#server);
+        }
+    });
+    if !services.is_empty() {
         content.extend(quote! {
-                include!(concat!(env!("OUT_DIR"), "/", #namespace, ".", #service_name, ".rs"));
+            // tonic names streaming types after the method route, i.e. `server_streamStream`.
+            #[allow(non_camel_case_types)]
+            mod #grpc_mod {
+                use super::*;
+                #(#tonic_includes)*
+            }
+            pub use #grpc_mod::*;
         });
     }
     // add types content
     content.extend(types_content);
-    content
+    content.extend(compile_table_refs(gen_ctx, types, files));
+    content.extend(crate::errors::compile_errors(services, wrappers));
+    content.extend(crate::policy::compile_policies(services, wrappers)?);
+    content.extend(crate::attributes::compile_attributes(
+        services,
+        default_package(builder),
+    )?);
+
+    if builder.generate_mocks {
+        content.extend(crate::mock::compile_mocks(
+            services,
+            default_package(builder),
+            wrappers,
+        ));
+    }
+    Ok(content)
 }
 
-fn write_tonic_flatbuffers_file(
-    out_dir: &Path,
-    content: &TokenStream,
-    package_file_suffix: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    use std::fs::File;
-    use std::io::Write;
+/// Modules re-exporting the namespace modules of the flatc output of each file.
+#[derive(Default)]
+struct NamespaceTree {
+    file_mods: Vec<syn::Ident>,
+    children: BTreeMap<String, NamespaceTree>,
+}
 
-    let dest_path = out_dir.join(format!("flatbuffers_tonic.{package_file_suffix}.rs"));
-    let mut f = File::create(&dest_path)?;
+impl NamespaceTree {
+    /// Re-export the namespace, i.e. `my_game::sample`, from the file module.
+    fn insert(&mut self, namespace_rs: &str, file_mod: &syn::Ident) {
+        let mut node = self;
+        for part in namespace_rs.split("::").filter(|p| !p.is_empty()) {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+        if !node.file_mods.contains(file_mod) {
+            node.file_mods.push(file_mod.clone());
+        }
+    }
 
-    // Parse TokenStream to syn::File and pretty-print
-    let syntax_tree: syn::File = syn::parse2(content.clone()).unwrap_or_else(|e| {
-        panic!("Failed to parse generated content to syntax tree: {e} : {content}");
-    });
-    let formatted = prettyplease::unparse(&syntax_tree);
-    f.write_all(formatted.as_bytes())?;
-    Ok(())
+    /// `path` is the namespace of this node, the file modules are next to the root.
+    fn to_tokens(&self, path: &[syn::Ident]) -> TokenStream {
+        let supers: TokenStream = path.iter().map(|_| quote! { super:: }).collect();
+        let file_mods = &self.file_mods;
+        let ns_path = quote! { #(#path::)* };
+        let mut tokens = quote! {
+            #(pub use #supers #file_mods::#ns_path*;)*
+        };
+        for (part, child) in &self.children {
+            let part = syn::parse_str::<syn::Ident>(part).unwrap();
+            let mut child_path = path.to_vec();
+            child_path.push(part.clone());
+            let inner = child.to_tokens(&child_path);
+            tokens.extend(quote! { pub mod #part { #inner } });
+        }
+        tokens
+    }
+}
+
+/// Client and server files of a service, `<package>.<Service>.rs` like tonic
+/// names them, `<Service>.rs` without a package.
+pub(crate) fn service_file_name(package: &str, service: &str) -> String {
+    if package.is_empty() {
+        format!("{service}.rs")
+    } else {
+        format!("{package}.{service}.rs")
+    }
+}
+
+/// Module name part for the flatc output of a fbs file.
+fn file_ident(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
 }
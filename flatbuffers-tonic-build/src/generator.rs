@@ -0,0 +1,167 @@
+// Generates the tonic code of a schema in memory, independent of cargo's OUT_DIR.
+
+use std::{collections::BTreeMap, path::Path};
+
+use flatbuffers_util::reflect::{GeneratorContext, reflection::reflection::Schema};
+use proc_macro2::TokenStream;
+
+use crate::{
+    Builder,
+    flatbuffers_tonic::{
+        CLIENT_CODEC, SERVER_CODEC, compile_flatbuffers_tonic_file, get_tonic_services,
+        owned_types, package_name, service_file_name,
+    },
+    ident::WrapperNames,
+};
+
+/// Generated source files by path, relative to the directory they are meant to be written to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeneratedFiles {
+    pub files: BTreeMap<String, String>,
+}
+
+impl GeneratedFiles {
+    /// Source of a generated file, i.e. `flatbuffers_tonic.sample.rs`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.files.get(name).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.files.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Write the files into `dir`, creating subdirectories as needed.
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> std::io::Result<()> {
+        for (name, source) in &self.files {
+            let path = dir.as_ref().join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, source)?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates the tonic code of a reflection schema, i.e. one compiled with
+/// `flatbuffers_util::reflect::compile_reflection_schema`.
+///
+/// The files are:
+/// * `flatbuffers_tonic.<package>.rs` to include, with `tonic::include_proto!("flatbuffers_tonic.<package>")`,
+/// * `client/<package>.<Service>.rs` and `server/<package>.<Service>.rs` included by it,
+///   `<Service>.rs` without a package.
+///
+/// It also includes the rust code flatc generates for the schema file, expected in
+/// `flatbuffers/<file stem>/` next to it. [`Builder::compile`] runs flatc and writes all of them
+/// to OUT_DIR, the generator is for snapshot tests of the generated code and other build systems.
+#[derive(Debug)]
+pub struct Generator {
+    gen_ctx: GeneratorContext,
+    files: Vec<String>,
+    builder: Builder,
+}
+
+impl Generator {
+    /// The schema needs the file names flatc stores with `--bfbs-filenames`.
+    pub fn from_schema(schema: &Schema) -> Result<Self, String> {
+        let gen_ctx = GeneratorContext::parse_from_schema(schema).map_err(|e| e.to_string())?;
+        let files = gen_ctx.root_file.clone().into_iter().collect();
+        Ok(Self::new(gen_ctx, files, Builder::new()))
+    }
+
+    pub(crate) fn new(gen_ctx: GeneratorContext, files: Vec<String>, builder: Builder) -> Self {
+        Generator {
+            gen_ctx,
+            files,
+            builder,
+        }
+    }
+
+    /// See [`Builder::generate_mocks`].
+    pub fn generate_mocks(mut self, enable: bool) -> Self {
+        self.builder = self.builder.generate_mocks(enable);
+        self
+    }
+
+    /// The flatc output was generated with `--gen-object-api`, see [`Builder::gen_object_api`].
+    pub fn gen_object_api(mut self, enable: bool) -> Self {
+        self.builder = self.builder.gen_object_api(enable);
+        self
+    }
+
+    /// See [`Builder::wrap_all_tables`].
+    pub fn wrap_all_tables(mut self, enable: bool) -> Self {
+        self.builder = self.builder.wrap_all_tables(enable);
+        self
+    }
+
+    /// See [`Builder::wrap_table`].
+    pub fn wrap_table(mut self, name: impl Into<String>) -> Self {
+        self.builder = self.builder.wrap_table(name);
+        self
+    }
+
+    /// See [`Builder::default_package`].
+    pub fn default_package(mut self, package: impl Into<String>) -> Self {
+        self.builder = self.builder.default_package(package);
+        self
+    }
+
+    pub fn generate(&self) -> Result<GeneratedFiles, String> {
+        if self.files.is_empty() {
+            return Err("the schema has no file name, compile it with --bfbs-filenames".into());
+        }
+        let types = owned_types(&self.gen_ctx, &self.builder, &self.files)?;
+        let services = self.gen_ctx.get_services();
+        let wrappers = WrapperNames::new(&types, &self.gen_ctx.get_namespace())?;
+
+        let mut generated = GeneratedFiles::default();
+        // Clients and servers are generated separately, as they use different codecs
+        // to report malformed messages with the right status code.
+        let code_gen = tonic_build::CodeGenBuilder::new();
+        for service in get_tonic_services(services, CLIENT_CODEC, &self.builder, &wrappers)? {
+            generated.files.insert(
+                format!("client/{}", tonic_file_name(&service)),
+                format_source(code_gen.generate_client(&service, ""))?,
+            );
+        }
+        for service in get_tonic_services(services, SERVER_CODEC, &self.builder, &wrappers)? {
+            generated.files.insert(
+                format!("server/{}", tonic_file_name(&service)),
+                format_source(code_gen.generate_server(&service, ""))?,
+            );
+        }
+
+        let content = compile_flatbuffers_tonic_file(
+            &self.gen_ctx,
+            &types,
+            &wrappers,
+            &self.builder,
+            &self.files,
+        )
+        .map_err(|e| format!("{}: {e}", self.files.join(", ")))?;
+        let package = package_name(&self.gen_ctx, &self.builder);
+        let file_name = if package.is_empty() {
+            "flatbuffers_tonic.rs".to_string()
+        } else {
+            format!("flatbuffers_tonic.{package}.rs")
+        };
+        let source = format!(
+            "// This file is generated by flatbuffers-tonic-build\n// Do not edit this file manually\n{}",
+            format_source(content)?
+        );
+        generated.files.insert(file_name, source);
+        Ok(generated)
+    }
+}
+
+fn tonic_file_name(service: &tonic_prost_build::manual::Service) -> String {
+    use tonic_build::Service;
+    service_file_name(service.package(), service.name())
+}
+
+fn format_source(content: TokenStream) -> Result<String, String> {
+    let syntax_tree: syn::File = syn::parse2(content.clone())
+        .map_err(|e| format!("generated code does not parse: {e}: {content}"))?;
+    Ok(prettyplease::unparse(&syntax_tree))
+}
@@ -0,0 +1,164 @@
+// Rust identifier rules for the generated code.
+
+use std::collections::HashMap;
+
+use flatbuffers_util::reflect::{MessageType, Service};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+
+/// Strict and reserved keywords of all editions.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Keywords that cannot be used as raw identifiers.
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name)
+}
+
+/// Rust name of an rpc method: snake_case without namespace prefix,
+/// keywords become raw identifiers, i.e. `type` -> `r#type`.
+pub(crate) fn method_ident(name: &str) -> String {
+    let name = name.rsplit('.').next().unwrap().to_snake_case();
+    if NON_RAW_KEYWORDS.contains(&name.as_str()) {
+        format!("{name}_")
+    } else if is_keyword(&name) {
+        format!("r#{name}")
+    } else {
+        name
+    }
+}
+
+/// UpperCamelCase for type names. Names that already are UpperCamelCase are kept as is,
+/// so `HTTPRequest` stays `HTTPRequest` instead of `HttpRequest`.
+fn upper_camel(name: &str) -> String {
+    let is_camel = name.starts_with(|c: char| c.is_ascii_uppercase()) && !name.contains('_');
+    if is_camel {
+        name.to_string()
+    } else {
+        name.to_upper_camel_case()
+    }
+}
+
+/// Name of the owned wrapper of a table, i.e. `sample_request` -> `OwnedSampleRequest`.
+pub(crate) fn wrapper_ident(fb_type: &str) -> String {
+    format!("Owned{}", upper_camel(fb_type))
+}
+
+/// Names of the owned wrappers of the tables of one generated file, by fully qualified name.
+/// A table sharing its name with another table of the file also gets its namespace in the
+/// name, unless it is in the namespace of the file, i.e. in the file of `v2` the wrapper of
+/// `v1.Request` is `OwnedV1Request` next to `OwnedRequest` of `v2.Request`.
+#[derive(Debug, Default)]
+pub(crate) struct WrapperNames(HashMap<String, String>);
+
+impl WrapperNames {
+    pub(crate) fn new(types: &[MessageType], namespace: &str) -> Result<Self, String> {
+        let names: HashMap<String, String> = types
+            .iter()
+            .map(|t| {
+                let shared = types
+                    .iter()
+                    .any(|o| o.fb_type == t.fb_type && o.full_name != t.full_name);
+                let name = match t.namespace.as_deref() {
+                    Some(ns) if shared && ns != namespace => {
+                        wrapper_ident(&format!("{}_{}", ns.replace('.', "_"), t.fb_type))
+                    }
+                    _ => wrapper_ident(&t.fb_type),
+                };
+                (t.full_name.clone(), name)
+            })
+            .collect();
+        check_collisions(
+            "types",
+            types
+                .iter()
+                .map(|t| (t.full_name.as_str(), names[&t.full_name].clone())),
+        )?;
+        Ok(Self(names))
+    }
+
+    /// Wrapper of a table by its fully qualified name.
+    pub(crate) fn of(&self, full_name: &str) -> String {
+        match self.0.get(full_name) {
+            Some(name) => name.clone(),
+            None => wrapper_ident(full_name.rsplit('.').next().unwrap()),
+        }
+    }
+
+    /// Wrapper of a table used by the service, named as in the schema.
+    pub(crate) fn get(&self, service: &Service, name: &str) -> String {
+        self.of(&service.qualified_type(name))
+    }
+}
+
+/// Name of the generated mock of a service, i.e. `Greeter` -> `MockGreeter`.
+pub(crate) fn mock_ident(service: &str) -> String {
+    format!("Mock{}", upper_camel(service))
+}
+
+/// Module name tonic uses for a service, `Greeter` -> `greeter`, for `greeter_server`.
+/// Same as tonic-build, which differs from heck for acronyms.
+pub(crate) fn tonic_snake_case(name: &str) -> String {
+    let mut s = String::new();
+    let mut it = name.chars().peekable();
+    while let Some(x) = it.next() {
+        s.push(x.to_ascii_lowercase());
+        if let Some(y) = it.peek()
+            && y.is_uppercase()
+        {
+            s.push('_');
+        }
+    }
+    s
+}
+
+/// Name flatc gives to the rust type of a table, which escapes keywords with a trailing underscore.
+pub(crate) fn flatc_type_ident(fb_type: &str) -> String {
+    if is_keyword(fb_type) {
+        format!("{fb_type}_")
+    } else {
+        fb_type.to_string()
+    }
+}
+
+/// gRPC package of a service: its namespace, or the default package if it has none.
+/// Empty for services without a package, their routes are `/<service>/<method>`.
+pub(crate) fn grpc_package<'a>(service: &'a Service, default_package: &'a str) -> &'a str {
+    service.namespace.as_deref().unwrap_or(default_package)
+}
+
+/// gRPC path of a method, i.e. `/sample.Sample/bidi_stream`.
+pub(crate) fn grpc_path(service: &Service, method: &str, default_package: &str) -> String {
+    match grpc_package(service, default_package) {
+        "" => format!("/{}/{method}", service.name),
+        package => format!("/{package}.{}/{method}", service.name),
+    }
+}
+
+/// Fails if two different names map to the same rust identifier,
+/// i.e. methods `SayHello` and `say_hello` in the same service.
+pub(crate) fn check_collisions<'a>(
+    what: &str,
+    names: impl IntoIterator<Item = (&'a str, String)>,
+) -> Result<(), String> {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for (name, ident) in names {
+        match seen.get(&ident) {
+            Some(prev) if *prev != name => {
+                return Err(format!(
+                    "{what} `{prev}` and `{name}` both map to rust identifier `{ident}`"
+                ));
+            }
+            _ => {
+                seen.insert(ident, name);
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,17 +1,134 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+pub(crate) mod attributes;
+pub(crate) mod errors;
 pub(crate) mod flatbuffers_self;
 pub(crate) mod flatbuffers_tonic;
+pub(crate) mod generator;
+pub(crate) mod ident;
+pub(crate) mod mock;
+pub(crate) mod policy;
+
+pub use generator::{GeneratedFiles, Generator};
+
+/// Configures code generation, [`compile_flatbuffers_tonic`] uses the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    generate_mocks: bool,
+    compat_baseline: Option<PathBuf>,
+    snapshot_dir: Option<PathBuf>,
+    locked_snapshots: Option<bool>,
+    default_package: Option<String>,
+    gen_object_api: bool,
+    flatc_args: Vec<String>,
+    include_paths: Vec<PathBuf>,
+    wrap_all_tables: bool,
+    wrapped_tables: Vec<String>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also generate a `Mock<Service>` in a `<service>_mock` module for each service.
+    /// The generated code needs the `testing` feature of flatbuffers-tonic.
+    pub fn generate_mocks(mut self, enable: bool) -> Self {
+        self.generate_mocks = enable;
+        self
+    }
+
+    /// Fail the build if a schema breaks wire compatibility with the file of the same
+    /// name in `dir`, i.e. a checkout of the last released schemas.
+    /// Files without a counterpart in `dir` are not checked.
+    pub fn check_compatibility(mut self, dir: impl AsRef<Path>) -> Self {
+        self.compat_baseline = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Keep a snapshot of the wire surface of each schema in `dir`, as `<file stem>.lock`,
+    /// meant to be committed like a lock file. The build fails if the schema breaks
+    /// compatibility with its snapshot, unless the `FLATBUFFERS_TONIC_ACCEPT_BREAKING`
+    /// env var is set. Otherwise the snapshot is created or updated to the schema,
+    /// see [`Builder::locked_snapshots`].
+    pub fn schema_snapshots(mut self, dir: impl AsRef<Path>) -> Self {
+        self.snapshot_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Fail the build with a diff when a snapshot is missing or out of date,
+    /// instead of writing it. Locked by default when the `CI` env var is set.
+    pub fn locked_snapshots(mut self, locked: bool) -> Self {
+        self.locked_snapshots = Some(locked);
+        self
+    }
+
+    /// gRPC package of services in schemas without a namespace, i.e. routes
+    /// `/<package>.Greeter/SayHello`, also naming the generated `flatbuffers_tonic.<package>.rs`.
+    /// Without it such services have routes like `/Greeter/SayHello`,
+    /// and are generated into `flatbuffers_tonic.rs`.
+    pub fn default_package(mut self, package: impl Into<String>) -> Self {
+        self.default_package = Some(package.into());
+        self
+    }
+
+    /// Have flatc generate the object API, i.e. `MonsterT`, and the `Owned*` wrappers
+    /// convert from and to it with `unpack()` and `From<&MonsterT>`.
+    pub fn gen_object_api(mut self, enable: bool) -> Self {
+        self.gen_object_api = enable;
+        self
+    }
+
+    /// Pass an argument to flatc when generating the rust code,
+    /// i.e. `--gen-name-strings` or `--rust-serialize`.
+    /// `--gen-object-api` is the same as [`Builder::gen_object_api`].
+    pub fn flatc_arg(mut self, arg: impl Into<String>) -> Self {
+        let arg = arg.into();
+        if arg == "--gen-object-api" {
+            return self.gen_object_api(true);
+        }
+        self.flatc_args.push(arg);
+        self
+    }
+
+    /// Search `dir` for fbs files in `include` declarations.
+    /// Pass `--gen-all` to generate the included types with the file including them.
+    pub fn include_path(mut self, dir: impl AsRef<Path>) -> Self {
+        self.include_paths.push(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Also generate `Owned*` wrappers for the tables of the compiled files
+    /// no rpc uses, i.e. to send them over other channels.
+    pub fn wrap_all_tables(mut self, enable: bool) -> Self {
+        self.wrap_all_tables = enable;
+        self
+    }
+
+    /// Also generate an `Owned*` wrapper for a table by fully qualified name, i.e. `sample.Monster`.
+    pub fn wrap_table(mut self, name: impl Into<String>) -> Self {
+        self.wrapped_tables.push(name.into());
+        self
+    }
+
+    /// Currently assumes fbs files are independent.
+    pub fn compile<P>(&self, fbs_path: &[P]) -> Result<(), Box<dyn std::error::Error>>
+    where
+        P: AsRef<Path>,
+    {
+        // Compile flatbuffers first
+        flatbuffers_self::compile_flat_buffer_self(fbs_path, self);
+
+        // Then compile tonic
+        flatbuffers_tonic::compile_flatbuffers_tonic_file_list_only(fbs_path, self)?;
+        Ok(())
+    }
+}
 
 /// Currently assumes fbs files are independent.
 pub fn compile_flatbuffers_tonic<P>(fbs_path: &[P]) -> Result<(), Box<dyn std::error::Error>>
 where
     P: AsRef<Path>,
 {
-    // Compile flatbuffers first
-    flatbuffers_self::compile_flat_buffer_self(fbs_path);
-
-    // Then compile tonic
-    flatbuffers_tonic::compile_flatbuffers_tonic_file_list_only(fbs_path)?;
-    Ok(())
+    Builder::new().compile(fbs_path)
 }
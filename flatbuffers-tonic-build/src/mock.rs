@@ -0,0 +1,130 @@
+// Generates a programmable mock for each service, see `flatbuffers_tonic::testing`.
+
+use flatbuffers_util::reflect::Service;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::ident::{WrapperNames, grpc_path, method_ident, mock_ident, tonic_snake_case};
+
+pub(crate) fn compile_mocks(
+    services: &[Service],
+    default_package: &str,
+    wrappers: &WrapperNames,
+) -> TokenStream {
+    let mut content = TokenStream::new();
+    for service in services {
+        content.extend(compile_mock(service, default_package, wrappers));
+    }
+    content
+}
+
+fn compile_mock(service: &Service, default_package: &str, wrappers: &WrapperNames) -> TokenStream {
+    let snake = tonic_snake_case(&service.name);
+    let mock_mod = format_ident!("{}_mock", snake);
+    let server_mod = format_ident!("{}_server", snake);
+    let client_mod = format_ident!("{}_client", snake);
+    let server_type = format_ident!("{}Server", service.name);
+    let client_type = format_ident!("{}Client", service.name);
+    let server_trait = format_ident!("{}", service.name);
+    let mock_type = format_ident!("{}", mock_ident(&service.name));
+    let doc = format!(" Mock of the `{}` service for tests.", service.name);
+
+    let mut fields = Vec::new();
+    let mut defaults = Vec::new();
+    let mut verifies = Vec::new();
+    let mut impls = Vec::new();
+    for method in &service.methods {
+        let name = format_ident!("{}", method_ident(&method.name));
+        let req = format_ident!("{}", wrappers.get(service, &method.request_name));
+        let resp = format_ident!("{}", wrappers.get(service, &method.response_name));
+        let path = grpc_path(service, &method.name, default_package);
+        fields.push(quote! {
+            pub #name: flatbuffers_tonic::testing::MockMethod<super::#req, super::#resp>
+        });
+        defaults.push(quote! {
+            #name: flatbuffers_tonic::testing::MockMethod::new(#path)
+        });
+        verifies.push(quote! { self.#name.verify(); });
+
+        let req_type = if method.client_streaming {
+            quote! { tonic::Streaming<super::#req> }
+        } else {
+            quote! { super::#req }
+        };
+        let call = match (method.client_streaming, method.server_streaming) {
+            (false, false) => quote! { unary },
+            (true, false) => quote! { client_streaming },
+            (false, true) => quote! { server_streaming },
+            (true, true) => quote! { streaming },
+        };
+        if method.server_streaming {
+            // tonic names the stream type after the route name.
+            let stream = format_ident!("{}Stream", method.name);
+            impls.push(quote! {
+                type #stream = flatbuffers_tonic::testing::MockStream<super::#resp>;
+
+                async fn #name(
+                    &self,
+                    request: tonic::Request<#req_type>,
+                ) -> std::result::Result<tonic::Response<Self::#stream>, tonic::Status> {
+                    self.#name.#call(request).await
+                }
+            });
+        } else {
+            impls.push(quote! {
+                async fn #name(
+                    &self,
+                    request: tonic::Request<#req_type>,
+                ) -> std::result::Result<tonic::Response<super::#resp>, tonic::Status> {
+                    self.#name.#call(request).await
+                }
+            });
+        }
+    }
+
+    quote! {
+        /// Generated mock implementations.
+        pub mod #mock_mod {
+            #[doc = #doc]
+            /// Clones share the programmed responses and recorded requests.
+            #[derive(Clone)]
+            pub struct #mock_type {
+                #(#fields,)*
+            }
+
+            impl Default for #mock_type {
+                fn default() -> Self {
+                    Self {
+                        #(#defaults,)*
+                    }
+                }
+            }
+
+            impl #mock_type {
+                pub fn new() -> Self {
+                    Self::default()
+                }
+
+                /// Panics if a method was not called as often as expected.
+                pub fn verify(&self) {
+                    #(#verifies)*
+                }
+
+                /// Serve the mock in process and return a client connected to it.
+                pub fn into_client(
+                    self,
+                ) -> super::#client_mod::#client_type<tonic::transport::Channel> {
+                    let routes = tonic::service::Routes::new(super::#server_mod::#server_type::new(self));
+                    super::#client_mod::#client_type::new(
+                        flatbuffers_tonic::testing::in_process_channel(routes),
+                    )
+                }
+            }
+
+            #[tonic::async_trait]
+            impl super::#server_mod::#server_trait for #mock_type {
+                #(#impls)*
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// Generates clients applying the deadline and retry policies of the
+// `timeout`, `idempotent` and `retry` method attributes.
+
+use std::time::Duration;
+
+use flatbuffers_util::reflect::{Method, Service};
+use heck::ToShoutySnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::ident::{WrapperNames, method_ident, tonic_snake_case};
+
+pub(crate) fn compile_policies(
+    services: &[Service],
+    wrappers: &WrapperNames,
+) -> Result<TokenStream, String> {
+    let mut content = TokenStream::new();
+    for service in services {
+        content.extend(compile_service_policies(service, wrappers)?);
+    }
+    Ok(content)
+}
+
+/// Parse a duration like `500ms`, `2s`, `1m` or `1h`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration `{value}`"))?;
+    let millis = match unit {
+        "ms" => Some(number),
+        "s" => number.checked_mul(1000),
+        "m" => number.checked_mul(60 * 1000),
+        "h" => number.checked_mul(60 * 60 * 1000),
+        _ => {
+            return Err(format!(
+                "invalid duration `{value}`, expected i.e. `500ms` or `2s`"
+            ));
+        }
+    };
+    millis
+        .map(Duration::from_millis)
+        .ok_or_else(|| format!("duration `{value}` is too long"))
+}
+
+fn method_policy(method: &Method) -> Result<TokenStream, String> {
+    let timeout = match &method.timeout {
+        Some(value) => {
+            let millis = parse_duration(value)?.as_millis() as u64;
+            quote! { Some(std::time::Duration::from_millis(#millis)) }
+        }
+        None => quote! { None },
+    };
+    if method.idempotent && !method.is_unary() {
+        return Err("streaming methods cannot be retried, remove `idempotent`".to_string());
+    }
+    let retries: u32 = match (&method.retry, method.idempotent) {
+        (Some(value), true) => value
+            .parse()
+            .map_err(|_| format!("invalid retry count `{value}`"))?,
+        (Some(_), false) => return Err("`retry` needs the `idempotent` attribute".to_string()),
+        (None, true) => {
+            return Ok(quote! {
+                flatbuffers_tonic::CallPolicy {
+                    timeout: #timeout,
+                    retries: flatbuffers_tonic::CallPolicy::DEFAULT_RETRIES,
+                }
+            });
+        }
+        (None, false) => 0,
+    };
+    Ok(quote! {
+        flatbuffers_tonic::CallPolicy {
+            timeout: #timeout,
+            retries: #retries,
+        }
+    })
+}
+
+fn compile_service_policies(
+    service: &Service,
+    wrappers: &WrapperNames,
+) -> Result<TokenStream, String> {
+    let has_policy = service
+        .methods
+        .iter()
+        .any(|m| m.timeout.is_some() || m.idempotent || m.retry.is_some());
+    if !has_policy {
+        return Ok(TokenStream::new());
+    }
+    let snake = tonic_snake_case(&service.name);
+    let policy_mod = format_ident!("{}_policy", snake);
+    let client_mod = format_ident!("{}_client", snake);
+    let client_type = format_ident!("{}Client", service.name);
+    let policy_client = format_ident!("{}PolicyClient", service.name);
+    let doc = format!(
+        " `{}Client` applying the call policy of each method.",
+        service.name
+    );
+
+    let mut consts = Vec::new();
+    let mut fns = Vec::new();
+    for method in &service.methods {
+        let policy = method_policy(method)
+            .map_err(|e| format!("method {}.{}: {e}", service.name, method.name))?;
+        let policy_name = format_ident!("{}", method.name.to_shouty_snake_case());
+        let policy_doc = format!(" Call policy of `{}`.", method.name);
+        consts.push(quote! {
+            #[doc = #policy_doc]
+            pub const #policy_name: flatbuffers_tonic::CallPolicy = #policy;
+        });
+
+        let name = format_ident!("{}", method_ident(&method.name));
+        let docs = &method.documentation;
+        let req = format_ident!("{}", wrappers.get(service, &method.request_name));
+        let resp = format_ident!("{}", wrappers.get(service, &method.response_name));
+        let resp_type = if method.server_streaming {
+            quote! { tonic::codec::Streaming<super::#resp> }
+        } else {
+            quote! { super::#resp }
+        };
+        let f = if method.client_streaming {
+            quote! {
+                #(#[doc = #docs])*
+                pub async fn #name(
+                    &mut self,
+                    request: impl tonic::IntoStreamingRequest<Message = super::#req>,
+                ) -> Result<tonic::Response<#resp_type>, tonic::Status> {
+                    let inner = &mut self.inner;
+                    #policy_name
+                        .call(request.into_streaming_request(), move |request| inner.#name(request))
+                        .await
+                }
+            }
+        } else if method.server_streaming {
+            quote! {
+                #(#[doc = #docs])*
+                pub async fn #name(
+                    &mut self,
+                    request: impl tonic::IntoRequest<super::#req>,
+                ) -> Result<tonic::Response<#resp_type>, tonic::Status> {
+                    let inner = &mut self.inner;
+                    #policy_name
+                        .call(request.into_request(), move |request| inner.#name(request))
+                        .await
+                }
+            }
+        } else {
+            quote! {
+                #(#[doc = #docs])*
+                pub async fn #name(
+                    &mut self,
+                    request: impl tonic::IntoRequest<super::#req>,
+                ) -> Result<tonic::Response<#resp_type>, tonic::Status> {
+                    let inner = &self.inner;
+                    #policy_name
+                        .unary(request.into_request(), |request| {
+                            let mut inner = inner.clone();
+                            async move { inner.#name(request).await }
+                        })
+                        .await
+                }
+            }
+        };
+        fns.push(f);
+    }
+
+    Ok(quote! {
+        /// Call policies from the `timeout`, `idempotent` and `retry` attributes.
+        pub mod #policy_mod {
+            #(#consts)*
+
+            #[doc = #doc]
+            #[derive(Debug, Clone)]
+            pub struct #policy_client<T> {
+                inner: super::#client_mod::#client_type<T>,
+            }
+
+            impl<T> #policy_client<T>
+            where
+                T: tonic::client::GrpcService<tonic::body::Body> + Clone,
+                T::Error: Into<tonic::codegen::StdError>,
+                T::ResponseBody: tonic::codegen::Body<Data = tonic::codegen::Bytes> + Send + 'static,
+                <T::ResponseBody as tonic::codegen::Body>::Error: Into<tonic::codegen::StdError> + Send,
+            {
+                pub fn new(inner: super::#client_mod::#client_type<T>) -> Self {
+                    Self { inner }
+                }
+
+                pub fn into_inner(self) -> super::#client_mod::#client_type<T> {
+                    self.inner
+                }
+
+                #(#fns)*
+            }
+        }
+    })
+}
@@ -1,7 +1,39 @@
 fn main() {
-    flatbuffers_tonic_build::compile_flatbuffers_tonic(&[
-        "../fbs/fbs.helloworld.fbs",
-        "../fbs/sample.fbs",
-    ])
-    .expect("flatbuffers tonic compilation failed");
+    flatbuffers_tonic_build::Builder::new()
+        .generate_mocks(true)
+        .schema_snapshots("../fbs/snapshots")
+        .compile(&[
+            "../fbs/fbs.helloworld.fbs",
+            "../fbs/sample.fbs",
+            "../fbs/naming.fbs",
+            "../fbs/MyGame.Sample.fbs",
+            "../fbs/acme/greeter.fbs",
+            "../fbs/acme/farewell.fbs",
+            "../fbs/no_namespace.fbs",
+            "../fbs/versions.fbs",
+            "../fbs/policy.fbs",
+        ])
+        .expect("flatbuffers tonic compilation failed");
+    flatbuffers_tonic_build::Builder::new()
+        .generate_mocks(true)
+        .schema_snapshots("../fbs/snapshots")
+        .default_package("vendor")
+        .compile(&["../fbs/vendor.fbs"])
+        .expect("flatbuffers tonic compilation failed");
+    flatbuffers_tonic_build::Builder::new()
+        .schema_snapshots("../fbs/snapshots")
+        .include_path("../fbs/include")
+        // Same as `gen_object_api(true)`, the wrappers get `unpack()` too.
+        .flatc_arg("--gen-object-api")
+        // Generates the included Point with objects.fbs.
+        .flatc_arg("--gen-all")
+        .flatc_arg("--gen-name-strings")
+        .wrap_all_tables(true)
+        .compile(&["../fbs/objects.fbs"])
+        .expect("flatbuffers tonic compilation failed");
+    flatbuffers_tonic_build::Builder::new()
+        .schema_snapshots("../fbs/snapshots")
+        .wrap_all_tables(true)
+        .compile(&["../fbs/records.fbs"])
+        .expect("flatbuffers tonic compilation failed");
 }
@@ -1,28 +1,30 @@
+use flatbuffers_tonic::{FBBuilder, OwnedFB, OwnedFBCodecable, StatusExt, status_with_details};
 use tonic::codec::Codec;
 
+use crate::generated::fbs::helloworld;
+
 // The reason to have wrapper struct is the inner struct has a lifetime
 // and it makes tonic generated code not compile. Simple struct wrapper
 // makes the type also explicit.
 mod wrappers {
-    use flatbuffers_tonic::OwnedFBCodecable;
-
     use crate::generated::fbs::helloworld;
 
-    pub struct OwnedHelloRequest(pub flatbuffers_tonic::OwnedFB<helloworld::HelloRequest<'static>>);
-
-    impl OwnedFBCodecable for OwnedHelloRequest {
-        fn new_from_bytes(buf: bytes::Bytes) -> Result<Self, flatbuffers::InvalidFlatbuffer> {
-            let owned =
-                flatbuffers_tonic::OwnedFB::<helloworld::HelloRequest>::new_from_bytes(buf)?;
-            Ok(Self(owned))
-        }
-
-        fn into_bytes(self) -> bytes::Bytes {
-            self.0.into_bytes()
-        }
+    flatbuffers_tonic::owned_wrapper! {
+        /// Same as the generated `OwnedHelloRequest`.
+        pub OwnedHelloRequest(helloworld::HelloRequest)
     }
 }
 
+fn hello_request(name: &str) -> OwnedFB<helloworld::HelloRequest<'static>> {
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string(name);
+    let req = helloworld::HelloRequest::create(
+        builder.get_mut(),
+        &helloworld::HelloRequestArgs { name: Some(name) },
+    );
+    builder.finish_owned(req)
+}
+
 #[test]
 fn codec_test() {
     let mut codec = flatbuffers_tonic::FlatBuffersCodec::<
@@ -31,4 +33,31 @@ fn codec_test() {
     >::new();
     let mut _encoder = codec.encoder();
     let mut _decoder = codec.decoder();
+
+    // Outside of tonic services the OwnedFB works without a wrapper.
+    let mut codec = flatbuffers_tonic::FlatBuffersCodec::<
+        OwnedFB<helloworld::HelloRequest<'static>>,
+        OwnedFB<helloworld::HelloReply<'static>>,
+    >::new();
+    let mut _encoder = codec.encoder();
+    let mut _decoder = codec.decoder();
+}
+
+#[test]
+fn test_owned_fb_codecable() {
+    let bytes = OwnedFBCodecable::into_bytes(hello_request("a"));
+    let owned = <OwnedFB<helloworld::HelloRequest>>::new_from_bytes(bytes.clone()).unwrap();
+    assert_eq!(owned.get_ref().name(), Some("a"));
+
+    let wrapper = wrappers::OwnedHelloRequest::new_from_bytes(bytes).unwrap();
+    assert_eq!(wrapper.get_ref().name(), Some("a"));
+    let wrapper: wrappers::OwnedHelloRequest = hello_request("b").into();
+    assert_eq!(wrapper.get_ref().name(), Some("b"));
+
+    let status = status_with_details(tonic::Code::NotFound, "missing", hello_request("c"));
+    let details = status
+        .fb_details::<OwnedFB<helloworld::HelloRequest>>()
+        .unwrap()
+        .unwrap();
+    assert_eq!(details.get_ref().name(), Some("c"));
 }
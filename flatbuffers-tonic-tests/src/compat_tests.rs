@@ -0,0 +1,177 @@
+use flatbuffers_util::reflect::{
+    compat::{BreakingChange, Snapshot, check_compatibility},
+    compile_reflection_schema,
+};
+
+fn changes(old: &str, new: &str) -> Vec<BreakingChange> {
+    let old = compile_reflection_schema(std::path::Path::new(old));
+    let new = compile_reflection_schema(std::path::Path::new(new));
+    check_compatibility(&old.get_ref(), &new.get_ref())
+}
+
+#[test]
+fn test_compatible_schema() {
+    assert_eq!(
+        changes("../fbs/compat/shop_v1.fbs", "../fbs/compat/shop_v2.fbs"),
+        []
+    );
+    assert_eq!(
+        changes("../fbs/compat/shop_v1.fbs", "../fbs/compat/shop_v1.fbs"),
+        []
+    );
+}
+
+#[test]
+fn test_breaking_schema() {
+    let changes = changes("../fbs/compat/shop_v1.fbs", "../fbs/compat/shop_v3.fbs");
+    for change in &changes {
+        println!("{change}");
+    }
+    let s = |v: &str| v.to_string();
+    assert_eq!(
+        changes,
+        [
+            BreakingChange::ResponseTypeChanged {
+                service: s("shop.Shop"),
+                method: s("Order"),
+                old: s("shop.order_reply"),
+                new: s("shop.status_reply"),
+            },
+            BreakingChange::StreamingChanged {
+                service: s("shop.Shop"),
+                method: s("Watch"),
+                old: s("server"),
+                new: s("bidi"),
+            },
+            BreakingChange::ResponseTypeChanged {
+                service: s("shop.Shop"),
+                method: s("Watch"),
+                old: s("shop.order_reply"),
+                new: s("shop.status_reply"),
+            },
+            BreakingChange::RpcRemoved {
+                service: s("shop.Shop"),
+                method: s("Cancel"),
+            },
+            BreakingChange::StructChanged {
+                name: s("shop.Point")
+            },
+            BreakingChange::FieldIdChanged {
+                table: s("shop.item"),
+                field: s("name"),
+                old: 0,
+                new: 1,
+            },
+            BreakingChange::FieldIdChanged {
+                table: s("shop.item"),
+                field: s("price"),
+                old: 1,
+                new: 0,
+            },
+            BreakingChange::TableRemoved {
+                table: s("shop.order_reply"),
+            },
+            BreakingChange::FieldRequiredChanged {
+                table: s("shop.order_request"),
+                field: s("items"),
+                required: true,
+            },
+            BreakingChange::FieldRemoved {
+                table: s("shop.order_request"),
+                field: s("note"),
+            },
+            BreakingChange::EnumTypeChanged {
+                name: s("shop.Color"),
+                old: s("byte"),
+                new: s("short"),
+            },
+            BreakingChange::EnumValueRemoved {
+                name: s("shop.Color"),
+                value: s("Green"),
+            },
+            BreakingChange::EnumValueChanged {
+                name: s("shop.Color"),
+                value: s("Blue"),
+                old: 2,
+                new: 3,
+            },
+            BreakingChange::UnionVariantChanged {
+                name: s("shop.Payment"),
+                value: s("card"),
+                old: s("shop.card_payment"),
+                new: s("shop.gift_card"),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    let v1 = compile_reflection_schema(std::path::Path::new("../fbs/compat/shop_v1.fbs"));
+    let v3 = compile_reflection_schema(std::path::Path::new("../fbs/compat/shop_v3.fbs"));
+    let snapshot = Snapshot::from_schema(&v1.get_ref());
+    let text = snapshot.to_string();
+    assert!(text.contains("  rpc Watch shop.order_request shop.order_reply server\n"));
+    assert!(text.contains("struct shop.Point 8\n"));
+    assert!(text.contains("  value Blue 2\n"));
+    assert!(text.contains("  value card 1 shop.card_payment\n"));
+
+    let parsed = Snapshot::parse(&text).unwrap();
+    assert_eq!(parsed, snapshot);
+    // Comparing with a stored snapshot finds the same changes as with the schema.
+    assert_eq!(
+        parsed.breaking_changes(&Snapshot::from_schema(&v3.get_ref())),
+        check_compatibility(&v1.get_ref(), &v3.get_ref())
+    );
+
+    let v3_snapshot = Snapshot::from_schema(&v3.get_ref());
+    let v3_text = v3_snapshot.to_string();
+    assert!(v3_text.contains("  field 0 items [shop.item] @4 required\n"));
+    assert_eq!(Snapshot::parse(&v3_text).unwrap(), v3_snapshot);
+    // Dropping `required` breaks old readers.
+    assert!(v3_snapshot.breaking_changes(&snapshot).contains(
+        &BreakingChange::FieldRequiredChanged {
+            table: "shop.order_request".to_string(),
+            field: "items".to_string(),
+            required: false,
+        }
+    ));
+
+    assert!(Snapshot::parse("table shop.item\n").is_err());
+}
+
+#[test]
+fn test_locked_snapshots() {
+    let dir = std::env::temp_dir().join(format!("fbs-locked-snapshots-{}", std::process::id()));
+    let compile = || {
+        flatbuffers_tonic_build::Builder::new()
+            .schema_snapshots(&dir)
+            .locked_snapshots(true)
+            .compile(&["../fbs/sample.fbs"])
+    };
+
+    // A missing snapshot is not created.
+    let err = compile().unwrap_err().to_string();
+    assert!(err.contains("sample.lock is out of date"), "{err}");
+    assert!(err.contains("  +service sample.Sample"), "{err}");
+    assert!(!dir.join("sample.lock").exists());
+
+    // Neither is a compatible change written.
+    let lock = std::fs::read_to_string("../fbs/snapshots/sample.lock").unwrap();
+    let outdated = lock.replace(
+        "  rpc say_hello2 sample.sample_request sample.sample_reply none\n",
+        "",
+    );
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("sample.lock"), &outdated).unwrap();
+    let err = compile().unwrap_err().to_string();
+    assert!(
+        err.contains("  +  rpc say_hello2 sample.sample_request sample.sample_reply none"),
+        "{err}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("sample.lock")).unwrap(),
+        outdated
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,213 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use flatbuffers_tonic::{
+    CompressionEncoding, CompressionLayer, Direction, InspectContext, InspectLayer,
+    testing::in_process_channel,
+};
+use tokio_stream::StreamExt;
+use tonic::{Status, service::Routes};
+use tower_layer::Layer;
+
+use crate::fixtures::{
+    client_stream_request, hello_request, hello_sample_mock, sample_mock, sample_request,
+    server_stream_request,
+};
+use crate::generated::{
+    greeter_client::GreeterClient, greeter_server::GreeterServer,
+    hello_sample_client::HelloSampleClient, hello_sample_server::HelloSampleServer,
+    sample_client::SampleClient, sample_server::SampleServer,
+};
+use crate::tonic_tests::Greeter;
+
+/// Records the uncompressed messages seen on the wire, compressed ones are skipped.
+type Seen = Arc<Mutex<Vec<(Direction, String)>>>;
+
+fn record(seen: Seen) -> InspectLayer<impl flatbuffers_tonic::Inspector> {
+    InspectLayer::new(
+        move |ctx: &InspectContext, message: Bytes| -> Result<Bytes, Status> {
+            if !ctx.compressed() {
+                seen.lock()
+                    .unwrap()
+                    .push((ctx.direction(), ctx.method().to_string()));
+            }
+            Ok(message)
+        },
+    )
+}
+
+/// Runs all four kinds of rpc with both sides compressing every message.
+async fn round_trip(encoding: CompressionEncoding) {
+    let seen = Seen::default();
+    let routes = Routes::new(
+        record(seen.clone()).layer(
+            SampleServer::new(sample_mock())
+                .accept_compressed(encoding)
+                .send_compressed(encoding),
+        ),
+    )
+    .add_service(
+        record(seen.clone()).layer(
+            HelloSampleServer::new(hello_sample_mock())
+                .accept_compressed(encoding)
+                .send_compressed(encoding),
+        ),
+    );
+    let ch = in_process_channel(routes);
+    let mut hello_client = HelloSampleClient::new(ch.clone())
+        .send_compressed(encoding)
+        .accept_compressed(encoding);
+    let mut client = SampleClient::new(ch)
+        .send_compressed(encoding)
+        .accept_compressed(encoding);
+
+    // unary
+    let reply = hello_client
+        .say_hello(sample_request("zip"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello zip"));
+
+    // client stream
+    let request_stream = tokio_stream::iter((0..10).map(client_stream_request));
+    let reply = client
+        .client_stream(request_stream)
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().count(), 10);
+
+    // server stream
+    let mut response = client
+        .server_stream(server_stream_request(5))
+        .await
+        .unwrap()
+        .into_inner();
+    let mut idx = 0;
+    while let Some(msg) = response.next().await {
+        let expected = format!("server response {idx}");
+        assert_eq!(msg.unwrap().get_ref().message(), Some(expected.as_str()));
+        idx += 1;
+    }
+    assert_eq!(idx, 5);
+
+    // bidi stream
+    let request_stream = tokio_stream::iter(0..5).map(|i| sample_request(&format!("name {i}")));
+    let mut response = client
+        .bidi_stream(request_stream)
+        .await
+        .unwrap()
+        .into_inner();
+    let mut idx = 0;
+    while let Some(msg) = response.next().await {
+        let expected = format!("hello name {idx}");
+        assert_eq!(msg.unwrap().get_ref().message(), Some(expected.as_str()));
+        idx += 1;
+    }
+    assert_eq!(idx, 5);
+
+    // every message went over the wire compressed
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_compression_gzip() {
+    round_trip(CompressionEncoding::Gzip).await;
+}
+
+#[tokio::test]
+async fn test_compression_zstd() {
+    round_trip(CompressionEncoding::Zstd).await;
+}
+
+#[tokio::test]
+async fn test_compression_threshold() {
+    let encoding = CompressionEncoding::Gzip;
+    let seen = Seen::default();
+    let routes = Routes::new(
+        record(seen.clone()).layer(
+            CompressionLayer::server(encoding)
+                .unwrap()
+                .threshold(256)
+                .layer(GreeterServer::new(Greeter {}).accept_compressed(encoding)),
+        ),
+    )
+    .add_service(
+        record(seen.clone()).layer(
+            CompressionLayer::server(encoding)
+                .unwrap()
+                .threshold(256)
+                .layer(SampleServer::new(sample_mock()).accept_compressed(encoding)),
+        ),
+    );
+
+    let ch = CompressionLayer::client(encoding)
+        .unwrap()
+        .threshold(256)
+        .layer(in_process_channel(routes));
+    let mut greeter = GreeterClient::new(ch.clone()).accept_compressed(encoding);
+    let mut client = SampleClient::new(ch).accept_compressed(encoding);
+
+    // small messages stay uncompressed
+    let reply = greeter
+        .say_hello(hello_request("small"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello small"));
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![
+            (Direction::Request, "SayHello".to_string()),
+            (Direction::Response, "SayHello".to_string())
+        ]
+    );
+    seen.lock().unwrap().clear();
+
+    // large messages are compressed both ways
+    let large = "large ".repeat(100);
+    let reply = greeter
+        .say_hello(hello_request(&large))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        reply.get_ref().message(),
+        Some(format!("hello {large}").as_str())
+    );
+    assert!(seen.lock().unwrap().is_empty());
+
+    // the threshold applies per message within a stream
+    let names = ["a".to_string(), large.clone(), "b".to_string(), large];
+    let request_stream = tokio_stream::iter(names.clone()).map(|name| sample_request(&name));
+    let mut response = client
+        .bidi_stream(request_stream)
+        .await
+        .unwrap()
+        .into_inner();
+    let mut replies = Vec::new();
+    while let Some(msg) = response.next().await {
+        replies.push(msg.unwrap().get_ref().message().unwrap().to_string());
+    }
+    assert_eq!(
+        replies,
+        names
+            .iter()
+            .map(|n| format!("hello {n}"))
+            .collect::<Vec<_>>()
+    );
+    let seen = seen.lock().unwrap().clone();
+    let count = |direction| seen.iter().filter(|(d, _)| *d == direction).count();
+    assert_eq!(count(Direction::Request), 2);
+    assert_eq!(count(Direction::Response), 2);
+}
+
+#[test]
+fn test_compression_layer_unsupported() {
+    // tonic knows deflate, but flatbuffers-tonic has no feature for it.
+    let err = CompressionLayer::client(CompressionEncoding::Deflate).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(err.to_string(), "deflate compression is not supported");
+    assert!(CompressionLayer::server(CompressionEncoding::Deflate).is_err());
+}
@@ -0,0 +1,123 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use flatbuffers_tonic::{
+    DecodeError, Direction, FBBuilder, FlatBuffersCodec, InspectContext, InspectService,
+    OwnedFBCodecable, Side, testing::TestServer,
+};
+use tonic::{Code, Status, codegen::http::uri::PathAndQuery, service::Routes};
+
+use crate::generated::{
+    self, OwnedHelloReply, OwnedHelloRequest, greeter_client::GreeterClient,
+    greeter_server::GreeterServer,
+};
+use crate::tonic_tests::Greeter;
+
+/// Sends bytes as they are, to get malformed buffers on the wire.
+struct RawMessage(Bytes);
+
+impl OwnedFBCodecable for RawMessage {
+    fn new_from_bytes(buf: Bytes) -> Result<Self, flatbuffers::InvalidFlatbuffer> {
+        Ok(Self(buf))
+    }
+
+    fn into_bytes(self) -> Bytes {
+        self.0
+    }
+}
+
+/// A HelloRequest or HelloReply whose string field is not utf8.
+fn invalid_utf8_hello() -> Bytes {
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string("zzzz");
+    let req = generated::fbs::helloworld::HelloRequest::create(
+        builder.get_mut(),
+        &generated::fbs::helloworld::HelloRequestArgs { name: Some(name) },
+    );
+    let mut buf = builder.finish_owned(req).into_bytes().to_vec();
+    let pos = buf.windows(4).position(|w| w == b"zzzz").unwrap();
+    buf[pos] = 0xff;
+    buf.into()
+}
+
+fn decode_error(status: &Status) -> &DecodeError {
+    std::error::Error::source(status)
+        .and_then(|e| e.downcast_ref::<DecodeError>())
+        .expect("status has a DecodeError source")
+}
+
+#[tokio::test]
+async fn test_malformed_messages() {
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    {
+        let errors = errors.clone();
+        flatbuffers_tonic::set_decode_error_hook(move |e: &DecodeError| {
+            if e.message_type().contains("Hello") {
+                errors.lock().unwrap().push((e.side(), e.path()));
+            }
+        });
+    }
+
+    // A malformed request is the fault of the client.
+    let server = TestServer::new(Routes::new(GreeterServer::new(Greeter {})));
+    let mut grpc = tonic::client::Grpc::new(server.channel());
+    grpc.ready().await.unwrap();
+    let Err(status) = grpc
+        .unary(
+            tonic::Request::new(RawMessage(invalid_utf8_hello())),
+            PathAndQuery::from_static("/fbs.helloworld.Greeter/SayHello"),
+            FlatBuffersCodec::<RawMessage, OwnedHelloReply>::new(),
+        )
+        .await
+    else {
+        panic!("expected the malformed request to fail");
+    };
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(
+        status.message().contains("at `name`"),
+        "{}",
+        status.message()
+    );
+    server.shutdown().await.unwrap();
+
+    // A malformed response is the fault of the server,
+    // the response is corrupted on the client before it is decoded.
+    let server = TestServer::new(Routes::new(GreeterServer::new(Greeter {})));
+    let corrupt = |ctx: &InspectContext, message: Bytes| -> Result<Bytes, Status> {
+        match ctx.direction() {
+            Direction::Request => Ok(message),
+            Direction::Response => Ok(invalid_utf8_hello()),
+        }
+    };
+    let mut client = GreeterClient::new(InspectService::new(server.channel(), corrupt));
+    let request: OwnedHelloRequest = {
+        let mut builder = FBBuilder::new();
+        let name = builder.get_mut().create_string("ok");
+        let req = generated::fbs::helloworld::HelloRequest::create(
+            builder.get_mut(),
+            &generated::fbs::helloworld::HelloRequestArgs { name: Some(name) },
+        );
+        builder.finish_owned(req).into()
+    };
+    let Err(status) = client.say_hello(request).await else {
+        panic!("expected the malformed response to fail");
+    };
+    assert_eq!(status.code(), Code::Internal);
+    let error = decode_error(&status);
+    assert_eq!(error.side(), Side::Client);
+    assert_eq!(error.path(), "message");
+    assert!(matches!(
+        error.error(),
+        flatbuffers::InvalidFlatbuffer::Utf8Error { .. }
+    ));
+    server.shutdown().await.unwrap();
+
+    let errors = errors.lock().unwrap();
+    assert_eq!(
+        *errors,
+        [
+            (Side::Server, "name".to_string()),
+            (Side::Client, "message".to_string())
+        ]
+    );
+}
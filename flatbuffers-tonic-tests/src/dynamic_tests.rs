@@ -0,0 +1,326 @@
+use flatbuffers_tonic::FBBuilder;
+use flatbuffers_util::reflect::{
+    DynamicError, DynamicMessage, DynamicTable, DynamicValue, Value, compile_reflection_schema,
+};
+
+use crate::generated::fbs::helloworld::{HelloRequest, HelloRequestArgs};
+
+fn vec3(x: f32, y: f32, z: f32) -> Value<'static> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&x.to_le_bytes());
+    bytes.extend_from_slice(&y.to_le_bytes());
+    bytes.extend_from_slice(&z.to_le_bytes());
+    Value::Struct(bytes)
+}
+
+#[test]
+fn test_dynamic_hello() {
+    let schema = compile_reflection_schema(std::path::Path::new("../fbs/fbs.helloworld.fbs"));
+    let schema = schema.get_ref();
+
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string("dynamic");
+    let req = HelloRequest::create(builder.get_mut(), &HelloRequestArgs { name: Some(name) });
+    let owned = builder.finish_owned(req);
+
+    let mut msg =
+        DynamicMessage::decode(&schema, "fbs.helloworld.HelloRequest", owned.get_slice()).unwrap();
+    assert_eq!(msg.type_name(), "fbs.helloworld.HelloRequest");
+    assert_eq!(msg.get("name").and_then(|v| v.as_str()), Some("dynamic"));
+
+    // rewrite the string
+    msg.set("name", Value::String("rewritten".to_string()))
+        .unwrap();
+    let owned = msg.encode::<HelloRequest>().unwrap();
+    assert_eq!(owned.get_ref().name(), Some("rewritten"));
+
+    // clear the field
+    msg.clear("name").unwrap();
+    assert!(!msg.has("name"));
+    let owned = msg.encode::<HelloRequest>().unwrap();
+    assert_eq!(owned.get_ref().name(), None);
+
+    // errors
+    assert!(matches!(
+        msg.set("name", Value::Int(1)),
+        Err(DynamicError::TypeMismatch(_))
+    ));
+    assert!(matches!(
+        msg.set("nope", Value::Int(1)),
+        Err(DynamicError::FieldNotFound(_))
+    ));
+    assert!(matches!(
+        DynamicMessage::new(&schema, "fbs.helloworld.Nope"),
+        Err(DynamicError::TypeNotFound(_))
+    ));
+    assert!(matches!(
+        DynamicMessage::decode(&schema, "fbs.helloworld.HelloRequest", &[1, 2]),
+        Err(DynamicError::InvalidFlatbuffer(_))
+    ));
+}
+
+#[test]
+fn test_dynamic_monster() {
+    let schema = compile_reflection_schema(std::path::Path::new("../fbs/dynamic.fbs"));
+    let schema = schema.get_ref();
+
+    let mut sword = DynamicMessage::new(&schema, "dynamic.Weapon").unwrap();
+    sword
+        .set("name", Value::String("sword".to_string()))
+        .unwrap();
+    sword.set("damage", Value::Short(3)).unwrap();
+    let mut axe = DynamicMessage::new(&schema, "dynamic.Weapon").unwrap();
+    axe.set("name", Value::String("axe".to_string())).unwrap();
+    let mut armor = DynamicMessage::new(&schema, "dynamic.Armor").unwrap();
+    armor.set("defense", Value::Int(7)).unwrap();
+
+    let mut monster = DynamicMessage::new(&schema, "dynamic.Monster").unwrap();
+    monster.set("pos", vec3(1.0, 2.0, 3.0)).unwrap();
+    monster
+        .set("name", Value::String("orc".to_string()))
+        .unwrap();
+    monster.set("hp", Value::Short(80)).unwrap();
+    monster.set("friendly", Value::Bool(true)).unwrap();
+    monster
+        .set(
+            "inventory",
+            Value::Vector(vec![Value::UByte(1), Value::UByte(2), Value::UByte(3)]),
+        )
+        .unwrap();
+    monster.set("color", Value::Byte(1)).unwrap();
+    monster
+        .set(
+            "weapons",
+            Value::Vector(vec![Value::Table(sword.clone()), Value::Table(axe)]),
+        )
+        .unwrap();
+    monster
+        .set("equipped", Value::Union(Box::new(armor)))
+        .unwrap();
+    monster
+        .set(
+            "path",
+            Value::Vector(vec![vec3(0.0, 0.0, 0.0), vec3(4.0, 5.0, 6.0)]),
+        )
+        .unwrap();
+    monster
+        .set(
+            "tags",
+            Value::Vector(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string()),
+            ]),
+        )
+        .unwrap();
+    monster.set("score", Value::Long(-9)).unwrap();
+
+    let buf = monster.encode_to_vec().unwrap();
+    let decoded = DynamicMessage::decode(&schema, "dynamic.Monster", &buf).unwrap();
+    assert_eq!(decoded, monster);
+    assert_eq!(decoded.get("pos"), Some(&vec3(1.0, 2.0, 3.0)));
+    assert_eq!(
+        decoded
+            .get("equipped")
+            .and_then(|v| v.as_message())
+            .map(|m| m.type_name()),
+        Some("dynamic.Armor")
+    );
+    // absent fields fall back to schema defaults
+    assert_eq!(decoded.get("mana"), None);
+    assert_eq!(decoded.get_or_default("mana"), Some(Value::Short(150)));
+    // union type field is derived from the value
+    assert!(decoded.get("equipped_type").is_none());
+    assert!(matches!(
+        monster.set("equipped", Value::Union(Box::new(monster.clone()))),
+        Err(DynamicError::TypeMismatch(_))
+    ));
+    assert!(matches!(
+        monster.set("equipped_type", Value::UByte(1)),
+        Err(DynamicError::TypeMismatch(_))
+    ));
+    assert!(matches!(
+        monster.set("pos", Value::Struct(vec![0; 4])),
+        Err(DynamicError::TypeMismatch(_))
+    ));
+
+    // the required field is enforced by the schema verifier
+    let mut nameless = decoded.clone();
+    nameless.clear("name").unwrap();
+    let buf = nameless.encode_to_vec().unwrap();
+    assert!(matches!(
+        DynamicMessage::decode(&schema, "dynamic.Monster", &buf),
+        Err(DynamicError::InvalidFlatbuffer(_))
+    ));
+
+    // merge a partial update
+    let mut update = DynamicMessage::new(&schema, "dynamic.Monster").unwrap();
+    update.set("hp", Value::Short(1)).unwrap();
+    let mut armor_update = DynamicMessage::new(&schema, "dynamic.Armor").unwrap();
+    armor_update.set("defense", Value::Int(9)).unwrap();
+    update
+        .set("equipped", Value::Union(Box::new(armor_update)))
+        .unwrap();
+    update
+        .set("weapons", Value::Vector(vec![Value::Table(sword)]))
+        .unwrap();
+    let mut merged = decoded.clone();
+    merged.merge(&update).unwrap();
+    assert_eq!(merged.get("hp"), Some(&Value::Short(1)));
+    assert_eq!(merged.get("name").and_then(|v| v.as_str()), Some("orc"));
+    assert_eq!(
+        merged
+            .get("weapons")
+            .and_then(|v| v.as_vector())
+            .map(|v| v.len()),
+        Some(1)
+    );
+    let equipped = merged.get("equipped").and_then(|v| v.as_message()).unwrap();
+    assert_eq!(equipped.get("defense"), Some(&Value::Int(9)));
+    let buf = merged.encode_to_vec().unwrap();
+    assert_eq!(
+        DynamicMessage::decode(&schema, "dynamic.Monster", &buf).unwrap(),
+        merged
+    );
+    assert!(matches!(
+        merged.merge(&equipped.clone()),
+        Err(DynamicError::TypeMismatch(_))
+    ));
+
+    // values changed in place are checked when encoding
+    let mut edited = merged.clone();
+    *edited.get_mut("hp").unwrap() = Value::Int(1);
+    assert!(matches!(
+        edited.encode_to_vec(),
+        Err(DynamicError::TypeMismatch(_))
+    ));
+    let mut edited = merged.clone();
+    match edited.get_mut("inventory").unwrap() {
+        Value::Vector(values) => values.push(Value::String("gem".to_string())),
+        other => panic!("unexpected {other:?}"),
+    }
+    assert!(matches!(
+        edited.encode_to_vec(),
+        Err(DynamicError::TypeMismatch(_))
+    ));
+    let mut edited = merged.clone();
+    let armor = edited
+        .get_mut("equipped")
+        .unwrap()
+        .as_message_mut()
+        .unwrap();
+    *armor.get_mut("defense").unwrap() = Value::Short(1);
+    assert!(matches!(
+        edited.encode_to_vec(),
+        Err(DynamicError::TypeMismatch(_))
+    ));
+}
+
+#[test]
+fn test_dynamic_table() {
+    let schema = compile_reflection_schema(std::path::Path::new("../fbs/dynamic.fbs"));
+    let schema = schema.get_ref();
+
+    let mut weapon = DynamicMessage::new(&schema, "dynamic.Weapon").unwrap();
+    weapon
+        .set("name", Value::String("bow".to_string()))
+        .unwrap();
+    let mut monster = DynamicMessage::new(&schema, "dynamic.Monster").unwrap();
+    monster
+        .set("name", Value::String("elf".to_string()))
+        .unwrap();
+    monster.set("pos", vec3(1.0, 2.0, 3.0)).unwrap();
+    monster
+        .set("weapons", Value::Vector(vec![Value::Table(weapon.clone())]))
+        .unwrap();
+    monster
+        .set("equipped", Value::Union(Box::new(weapon.clone())))
+        .unwrap();
+    monster
+        .set(
+            "inventory",
+            Value::Vector(vec![Value::UByte(4), Value::UByte(5)]),
+        )
+        .unwrap();
+    let buf = monster.encode_to_vec().unwrap();
+
+    let table = DynamicTable::new(&buf, &schema, "dynamic.Monster").unwrap();
+    assert_eq!(table.type_name(), "dynamic.Monster");
+    assert_eq!(
+        table.get("name").unwrap().and_then(|v| v.as_str()),
+        Some("elf")
+    );
+    assert!(table.get("hp").unwrap().is_none());
+    assert!(matches!(
+        table.get("nope"),
+        Err(DynamicError::FieldNotFound(_))
+    ));
+
+    let pos = table.get("pos").unwrap().unwrap().as_struct().unwrap();
+    assert_eq!(pos.type_name(), "dynamic.Vec3");
+    assert_eq!(pos.get("y").unwrap().as_f64(), Some(2.0));
+
+    let inventory = table
+        .get("inventory")
+        .unwrap()
+        .unwrap()
+        .as_vector()
+        .unwrap();
+    assert_eq!(
+        inventory
+            .iter()
+            .filter_map(|v| v.as_i64())
+            .collect::<Vec<_>>(),
+        vec![4, 5]
+    );
+
+    let weapons = table.get("weapons").unwrap().unwrap().as_vector().unwrap();
+    assert_eq!(weapons.len(), 1);
+    let bow = weapons.get(0).unwrap().as_table().unwrap();
+    assert_eq!(
+        bow.get("name").unwrap().and_then(|v| v.as_str()),
+        Some("bow")
+    );
+    assert!(weapons.get(1).is_none());
+
+    let equipped = table.get("equipped").unwrap().unwrap();
+    assert!(matches!(equipped, DynamicValue::Union(t) if t.type_name() == "dynamic.Weapon"));
+
+    // union type fields are skipped
+    let names = table.fields().map(|(f, _)| f.name()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["pos", "name", "inventory", "weapons", "equipped"]
+    );
+
+    let mut paths = Vec::new();
+    table.walk(&mut |path, value| {
+        if value.as_str().is_some() || value.as_f64().is_some() {
+            paths.push(path.to_string());
+        }
+    });
+    assert_eq!(
+        paths,
+        vec![
+            "pos.x",
+            "pos.y",
+            "pos.z",
+            "name",
+            "weapons[0].name",
+            "equipped.name"
+        ]
+    );
+
+    assert_eq!(table.to_message().unwrap(), monster);
+
+    // any table can be the root, not just the schema root_type
+    let buf = weapon.encode_to_vec().unwrap();
+    let table = DynamicTable::new(&buf, &schema, "dynamic.Weapon").unwrap();
+    assert_eq!(
+        table.get("name").unwrap().and_then(|v| v.as_str()),
+        Some("bow")
+    );
+    assert!(matches!(
+        DynamicTable::new(&buf, &schema, "dynamic.Monster"),
+        Err(DynamicError::InvalidFlatbuffer(_))
+    ));
+}
@@ -122,3 +122,39 @@ fn fbs_builder_mismatch_test() {
     });
     assert!(err.is_err());
 }
+
+#[test]
+fn fbs_mutate_test() {
+    use crate::generated::sample::{client_stream_request, client_stream_requestArgs};
+
+    let mut fb_builder = FBBuilder::<client_stream_request>::new();
+    let req = client_stream_request::create(
+        fb_builder.get_mut(),
+        &client_stream_requestArgs { index: 1 },
+    );
+    let mut owned = fb_builder.finish_owned(req);
+    {
+        let mut req_mut = owned.get_mut();
+        let ok = unsafe { req_mut.set_scalar::<i32>(client_stream_request::VT_INDEX, 42) };
+        assert!(ok);
+    }
+    assert_eq!(owned.get_ref().index(), 42);
+    // The mutated bytes are still valid.
+    let owned = OwnedFB::<client_stream_request>::new_from_bytes(owned.into_bytes()).unwrap();
+    assert_eq!(owned.get_ref().index(), 42);
+
+    // Default values are not stored in the buffer, so they cannot be mutated.
+    let mut fb_builder = FBBuilder::<client_stream_request>::new();
+    let req = client_stream_request::create(
+        fb_builder.get_mut(),
+        &client_stream_requestArgs { index: 0 },
+    );
+    let mut owned = fb_builder.finish_owned(req);
+    let ok = unsafe {
+        owned
+            .get_mut()
+            .set_scalar::<i32>(client_stream_request::VT_INDEX, 42)
+    };
+    assert!(!ok);
+    assert_eq!(owned.get_ref().index(), 0);
+}
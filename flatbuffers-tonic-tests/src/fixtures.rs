@@ -0,0 +1,135 @@
+//! Messages and services shared by the tests.
+
+use flatbuffers_tonic::FBBuilder;
+
+use crate::generated::{
+    self, OwnedClientStreamRequest, OwnedClientStreamResponse, OwnedHelloReply, OwnedHelloRequest,
+    OwnedSampleError, OwnedSampleReply, OwnedSampleRequest, OwnedServerStreamRequest,
+    OwnedServerStreamResponse, hello_sample_mock::MockHelloSample, sample_mock::MockSample,
+};
+
+pub fn hello_request(name: &str) -> OwnedHelloRequest {
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string(name);
+    let req = generated::fbs::helloworld::HelloRequest::create(
+        builder.get_mut(),
+        &generated::fbs::helloworld::HelloRequestArgs { name: Some(name) },
+    );
+    builder.finish_owned(req).into()
+}
+
+pub fn hello_reply(message: &str) -> OwnedHelloReply {
+    let mut builder = FBBuilder::new();
+    let message = builder.get_mut().create_string(message);
+    let reply = generated::fbs::helloworld::HelloReply::create(
+        builder.get_mut(),
+        &generated::fbs::helloworld::HelloReplyArgs {
+            message: Some(message),
+        },
+    );
+    builder.finish_owned(reply).into()
+}
+
+pub fn sample_request(name: &str) -> OwnedSampleRequest {
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string(name);
+    let req = generated::sample::sample_request::create(
+        builder.get_mut(),
+        &generated::sample::sample_requestArgs { name: Some(name) },
+    );
+    builder.finish_owned(req).into()
+}
+
+pub fn sample_reply(message: &str) -> OwnedSampleReply {
+    let mut builder = FBBuilder::new();
+    let message = builder.get_mut().create_string(message);
+    let reply = generated::sample::sample_reply::create(
+        builder.get_mut(),
+        &generated::sample::sample_replyArgs {
+            message: Some(message),
+        },
+    );
+    builder.finish_owned(reply).into()
+}
+
+pub fn sample_error(reason: &str, retry_after_ms: i32) -> OwnedSampleError {
+    let mut builder = FBBuilder::new();
+    let reason = builder.get_mut().create_string(reason);
+    let err = generated::sample::sample_error::create(
+        builder.get_mut(),
+        &generated::sample::sample_errorArgs {
+            reason: Some(reason),
+            retry_after_ms,
+        },
+    );
+    builder.finish_owned(err).into()
+}
+
+pub fn client_stream_request(index: i32) -> OwnedClientStreamRequest {
+    let mut builder = FBBuilder::new();
+    let req = generated::sample::client_stream_request::create(
+        builder.get_mut(),
+        &generated::sample::client_stream_requestArgs { index },
+    );
+    builder.finish_owned(req).into()
+}
+
+pub fn client_stream_response(count: i32) -> OwnedClientStreamResponse {
+    let mut builder = FBBuilder::new();
+    let resp = generated::sample::client_stream_response::create(
+        builder.get_mut(),
+        &generated::sample::client_stream_responseArgs { count },
+    );
+    builder.finish_owned(resp).into()
+}
+
+pub fn server_stream_request(count: i32) -> OwnedServerStreamRequest {
+    let mut builder = FBBuilder::new();
+    let req = generated::sample::server_stream_request::create(
+        builder.get_mut(),
+        &generated::sample::server_stream_requestArgs { count },
+    );
+    builder.finish_owned(req).into()
+}
+
+pub fn server_stream_response(message: &str) -> OwnedServerStreamResponse {
+    let mut builder = FBBuilder::new();
+    let message = builder.get_mut().create_string(message);
+    let resp = generated::sample::server_stream_response::create(
+        builder.get_mut(),
+        &generated::sample::server_stream_responseArgs {
+            message: Some(message),
+        },
+    );
+    builder.finish_owned(resp).into()
+}
+
+fn hello(request: &OwnedSampleRequest) -> OwnedSampleReply {
+    sample_reply(&format!("hello {}", request.get_ref().name().unwrap_or("")))
+}
+
+/// `HelloSample` answering `hello <name>` on both methods.
+pub fn hello_sample_mock() -> MockHelloSample {
+    let mock = MockHelloSample::new();
+    mock.say_hello
+        .respond_with(|requests| Ok(requests.iter().map(hello).collect()));
+    mock.say_hello2
+        .respond_with(|requests| Ok(requests.iter().map(hello).collect()));
+    mock
+}
+
+/// `Sample` counting client streams, streaming `server response <i>` for the requested
+/// count and answering `hello <name>` to each bidi message.
+pub fn sample_mock() -> MockSample {
+    let mock = MockSample::new();
+    mock.client_stream
+        .respond_with(|requests| Ok(vec![client_stream_response(requests.len() as i32)]));
+    mock.server_stream.respond_with(|requests| {
+        Ok((0..requests[0].get_ref().count())
+            .map(|i| server_stream_response(&format!("server response {i}")))
+            .collect())
+    });
+    mock.bidi_stream
+        .respond_with(|requests| Ok(requests.iter().map(hello).collect()));
+    mock
+}
@@ -1,4 +1,40 @@
-// flatbuffers code has warnings.
-#![allow(warnings)]
 tonic::include_proto!("flatbuffers_tonic.fbs.helloworld");
 tonic::include_proto!("flatbuffers_tonic.sample");
+tonic::include_proto!("flatbuffers_tonic.naming");
+tonic::include_proto!("flatbuffers_tonic.MyGame.Sample");
+
+/// Services of greeter.fbs and farewell.fbs, both in namespace `acme.greeter`,
+/// kept apart as the `Greeter` name is taken by fbs.helloworld.
+pub mod acme_greeter {
+    tonic::include_proto!("flatbuffers_tonic.acme.greeter");
+}
+
+/// Services of no_namespace.fbs, without a gRPC package.
+pub mod plain {
+    tonic::include_proto!("flatbuffers_tonic");
+}
+
+/// Services of vendor.fbs, without namespace but built with the default package `vendor`.
+pub mod vendor {
+    tonic::include_proto!("flatbuffers_tonic.vendor");
+}
+
+/// Services of objects.fbs, built with the object API.
+pub mod objects {
+    tonic::include_proto!("flatbuffers_tonic.objects");
+}
+
+/// Services of versions.fbs, using `v1.Request` next to `v2.Request`.
+pub mod versions {
+    tonic::include_proto!("flatbuffers_tonic.v2");
+}
+
+/// Services of policy.fbs, with deadlines and retries.
+pub mod policy {
+    tonic::include_proto!("flatbuffers_tonic.policy");
+}
+
+/// Wrappers of records.fbs, a schema without services.
+pub mod records {
+    tonic::include_proto!("flatbuffers_tonic.records");
+}
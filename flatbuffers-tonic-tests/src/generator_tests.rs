@@ -0,0 +1,221 @@
+use flatbuffers_tonic_build::Generator;
+use flatbuffers_util::reflect::{
+    compile_reflection_schema, compile_reflection_schema_with_includes,
+};
+
+#[test]
+fn test_generate_sample() {
+    let schema = compile_reflection_schema(std::path::Path::new("../fbs/sample.fbs"));
+    let generated = Generator::from_schema(&schema.get_ref())
+        .unwrap()
+        .generate_mocks(true)
+        .generate()
+        .unwrap();
+    let names: Vec<_> = generated.iter().map(|(name, _)| name).collect();
+    assert_eq!(
+        names,
+        [
+            "client/sample.HelloSample.rs",
+            "client/sample.Sample.rs",
+            "flatbuffers_tonic.sample.rs",
+            "server/sample.HelloSample.rs",
+            "server/sample.Sample.rs",
+        ]
+    );
+    // Same as the build script output.
+    assert_eq!(
+        generated.get("flatbuffers_tonic.sample.rs"),
+        Some(include_str!(concat!(
+            env!("OUT_DIR"),
+            "/flatbuffers_tonic.sample.rs"
+        )))
+    );
+    assert_eq!(
+        generated.get("server/sample.HelloSample.rs"),
+        Some(include_str!(concat!(
+            env!("OUT_DIR"),
+            "/server/sample.HelloSample.rs"
+        )))
+    );
+    let source = generated.get("flatbuffers_tonic.sample.rs").unwrap();
+    assert!(source.contains("include!(\"flatbuffers/sample/mod.rs\");"));
+    assert!(source.contains("include!(\"client/sample.HelloSample.rs\");"));
+    assert!(source.contains("OwnedSampleRequest(sample::sample_request)"));
+}
+
+#[test]
+fn test_generate_default_package() {
+    let schema = compile_reflection_schema(std::path::Path::new("../fbs/no_namespace.fbs"));
+    let generator = Generator::from_schema(&schema.get_ref()).unwrap();
+    let generated = generator.generate().unwrap();
+    assert!(generated.get("flatbuffers_tonic.rs").is_some());
+    assert!(generated.get("client/Plain.rs").is_some());
+    let source = generated.get("flatbuffers_tonic.rs").unwrap();
+    assert!(source.contains("include!(\"client/Plain.rs\");"));
+
+    let generated = generator.default_package("vendor").generate().unwrap();
+    assert!(generated.get("flatbuffers_tonic.vendor.rs").is_some());
+    assert!(
+        generated
+            .get("server/vendor.Plain.rs")
+            .unwrap()
+            .contains("\"/vendor.Plain/Echo\"")
+    );
+}
+
+#[test]
+fn test_generate_write_to() {
+    let schema = compile_reflection_schema(std::path::Path::new("../fbs/fbs.helloworld.fbs"));
+    let generated = Generator::from_schema(&schema.get_ref())
+        .unwrap()
+        .generate()
+        .unwrap();
+    let dir = std::env::temp_dir().join(format!("flatbuffers-tonic-gen-{}", std::process::id()));
+    generated.write_to(&dir).unwrap();
+    for (name, source) in generated.iter() {
+        assert_eq!(std::fs::read_to_string(dir.join(name)).unwrap(), source);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_generate_wrapped_tables() {
+    let schema = compile_reflection_schema_with_includes(
+        std::path::Path::new("../fbs/objects.fbs"),
+        &["../fbs/include"],
+    );
+    let schema = schema.get_ref();
+    let generate = |generator: Generator| {
+        generator.generate().map(|generated| {
+            generated
+                .get("flatbuffers_tonic.objects.rs")
+                .unwrap()
+                .to_string()
+        })
+    };
+
+    let source = generate(Generator::from_schema(&schema).unwrap()).unwrap();
+    assert!(source.contains("OwnedShape(objects::Shape)"));
+    assert!(!source.contains("OwnedDrawing(objects::Drawing)"));
+
+    let source = generate(
+        Generator::from_schema(&schema)
+            .unwrap()
+            .wrap_table("objects.Drawing"),
+    )
+    .unwrap();
+    assert!(source.contains("OwnedDrawing(objects::Drawing)"));
+    assert!(source.contains("#[doc = \" Shapes stored together, not used by an rpc.\"]"));
+
+    let source = generate(
+        Generator::from_schema(&schema)
+            .unwrap()
+            .wrap_all_tables(true),
+    )
+    .unwrap();
+    assert!(source.contains("OwnedDrawing(objects::Drawing)"));
+    // Structs have no owned wrappers.
+    assert!(!source.contains("OwnedPoint"));
+
+    let err = generate(
+        Generator::from_schema(&schema)
+            .unwrap()
+            .wrap_table("objects.Point"),
+    )
+    .unwrap_err();
+    assert!(err.contains("is a struct"), "{err}");
+    let err = generate(
+        Generator::from_schema(&schema)
+            .unwrap()
+            .wrap_table("objects.Circle"),
+    )
+    .unwrap_err();
+    assert!(err.contains("is not in the schema"), "{err}");
+}
+
+#[test]
+fn test_generate_without_services() {
+    let schema = compile_reflection_schema(std::path::Path::new("../fbs/records.fbs"));
+    let generated = Generator::from_schema(&schema.get_ref())
+        .unwrap()
+        .wrap_all_tables(true)
+        .generate()
+        .unwrap();
+    let names: Vec<_> = generated.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["flatbuffers_tonic.records.rs"]);
+    let source = generated.get("flatbuffers_tonic.records.rs").unwrap();
+    assert!(source.contains("OwnedRecord(records::Record)"));
+    assert!(!source.contains("grpc_records"));
+
+    // Without services the namespace of the tables has to be unique.
+    let dir = std::env::temp_dir().join(format!("flatbuffers-tonic-nsvc-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("two_namespaces.fbs");
+    std::fs::write(
+        &path,
+        "namespace one;\ntable A { x: int; }\nnamespace two;\ntable B { y: int; }\n",
+    )
+    .unwrap();
+    let schema = compile_reflection_schema(&path);
+    let err = Generator::from_schema(&schema.get_ref())
+        .unwrap()
+        .wrap_all_tables(true)
+        .generate()
+        .unwrap_err();
+    assert!(err.contains("declares types in one, two"), "{err}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_wrapper_without_services() {
+    use crate::generated::records::{
+        OwnedRecord,
+        records::{Record, RecordArgs},
+    };
+    use flatbuffers_tonic::{FBBuilder, OwnedFBCodecable};
+
+    let mut builder = FBBuilder::<Record>::new();
+    let key = builder.get_mut().create_string("user-1");
+    let root = Record::create(
+        builder.get_mut(),
+        &RecordArgs {
+            key: Some(key),
+            value: None,
+        },
+    );
+    let bytes = OwnedRecord::from(builder.finish_owned(root)).into_bytes();
+    let record = OwnedRecord::new_from_bytes(bytes).unwrap();
+    assert_eq!(record.get_ref().key(), Some("user-1"));
+}
+
+#[test]
+fn test_generate_invalid_error_type() {
+    let dir = std::env::temp_dir().join(format!("flatbuffers-tonic-error-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let generate = |error: &str| {
+        let path = dir.join("bad.fbs");
+        std::fs::write(
+            &path,
+            format!(
+                "namespace bad;\nattribute \"error\";\nstruct Pos {{ x: int; }}\n\
+                 enum Kind : byte {{ A }}\ntable Req {{ x: int; }}\n\
+                 rpc_service Svc {{ Call(Req): Req (error: \"{error}\"); }}\n"
+            ),
+        )
+        .unwrap();
+        let schema = compile_reflection_schema(&path);
+        Generator::from_schema(&schema.get_ref())
+            .unwrap()
+            .generate()
+            .map(|_| ())
+    };
+    for error in ["Missing", "Pos", "Kind"] {
+        let err = generate(error).unwrap_err();
+        assert_eq!(
+            err,
+            format!("rpc Svc.Call: error type {error} is not a table in the schema")
+        );
+    }
+    generate("Req").unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
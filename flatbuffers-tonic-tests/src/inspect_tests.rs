@@ -0,0 +1,234 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use flatbuffers_tonic::{
+    CompressionEncoding, Direction, InspectContext, InspectLayer, InspectService, OwnedFBCodecable,
+    testing::{TestServer, connect},
+};
+use tokio_stream::StreamExt;
+use tonic::{Code, Status, service::Routes};
+use tower_layer::Layer;
+
+use crate::fixtures::{
+    client_stream_request, hello_request, sample_mock, sample_request, server_stream_request,
+};
+use crate::generated::{
+    OwnedClientStreamRequest, OwnedHelloRequest, OwnedSampleReply, OwnedServerStreamResponse,
+    greeter_client::GreeterClient, greeter_server::GreeterServer, sample_client::SampleClient,
+    sample_server::SampleServer,
+};
+use crate::tonic_tests::Greeter;
+
+/// Redacts `secret` and rejects `evil` names of hello requests.
+fn redact(ctx: &InspectContext, message: Bytes) -> Result<Bytes, Status> {
+    if ctx.direction() == Direction::Response {
+        return Ok(message);
+    }
+    let req = ctx.decode::<OwnedHelloRequest>(&message)?;
+    match req.get_ref().name() {
+        Some("evil") => Err(Status::permission_denied("evil name")),
+        Some("secret") => Ok(hello_request("***").into_bytes()),
+        _ => Ok(message),
+    }
+}
+
+#[tokio::test]
+async fn test_inspect_unary() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    // log every message, redact secrets and reject evil requests on the server
+    let inspector = {
+        let log = log.clone();
+        move |ctx: &InspectContext, message: Bytes| -> Result<Bytes, Status> {
+            log.lock()
+                .unwrap()
+                .push((ctx.method().to_string(), ctx.direction()));
+            redact(ctx, message)
+        }
+    };
+    let (server, mut client) = connect(
+        InspectLayer::new(inspector).layer(GreeterServer::new(Greeter {})),
+        GreeterClient::new,
+    );
+
+    let reply = client
+        .say_hello(hello_request("secret"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello ***"));
+
+    let reply = client
+        .say_hello(hello_request("tonic fbs"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello tonic fbs"));
+
+    let Err(status) = client.say_hello(hello_request("evil")).await else {
+        panic!("evil request should be rejected");
+    };
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(status.message(), "evil name");
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            ("SayHello".to_string(), Direction::Request),
+            ("SayHello".to_string(), Direction::Response),
+            ("SayHello".to_string(), Direction::Request),
+            ("SayHello".to_string(), Direction::Response),
+            ("SayHello".to_string(), Direction::Request),
+        ]
+    );
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_inspect_compressed() {
+    let encoding = CompressionEncoding::Gzip;
+    let compressed = Arc::new(Mutex::new(Vec::new()));
+    let inspector = {
+        let compressed = compressed.clone();
+        move |ctx: &InspectContext, message: Bytes| -> Result<Bytes, Status> {
+            compressed.lock().unwrap().push(ctx.compressed());
+            redact(ctx, message)
+        }
+    };
+    let (server, client) = connect(
+        InspectLayer::new(inspector).layer(
+            GreeterServer::new(Greeter {})
+                .accept_compressed(encoding)
+                .send_compressed(encoding),
+        ),
+        GreeterClient::new,
+    );
+    let mut client = client.send_compressed(encoding).accept_compressed(encoding);
+
+    // compressed messages are inspected decompressed, and the redaction is compressed again
+    let reply = client
+        .say_hello(hello_request("secret"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello ***"));
+    let Err(status) = client.say_hello(hello_request("evil")).await else {
+        panic!("evil request should be rejected");
+    };
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(*compressed.lock().unwrap(), vec![true, true, true]);
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_inspect_max_message_size() {
+    let (server, mut client) = connect(
+        InspectLayer::new(|_: &InspectContext, message: Bytes| Ok(message))
+            .max_message_size(64)
+            .layer(GreeterServer::new(Greeter {})),
+        GreeterClient::new,
+    );
+
+    let reply = client
+        .say_hello(hello_request("small"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello small"));
+
+    let Err(status) = client.say_hello(hello_request(&"large ".repeat(20))).await else {
+        panic!("large request should be rejected");
+    };
+    assert_eq!(status.code(), Code::ResourceExhausted);
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_inspect_streaming() {
+    // server side: reject the client stream at chunk 3 and cut server streams after 2 messages
+    let server_inspector = |ctx: &InspectContext, message: Bytes| -> Result<Bytes, Status> {
+        assert_eq!(ctx.service(), "sample.Sample");
+        match (ctx.method(), ctx.direction()) {
+            ("client_stream", Direction::Request) => {
+                let req = ctx.decode::<OwnedClientStreamRequest>(&message)?;
+                assert_eq!(req.get_ref().index() as usize, ctx.index());
+                if req.get_ref().index() == 3 {
+                    return Err(Status::invalid_argument("chunk 3 rejected"));
+                }
+            }
+            ("server_stream", Direction::Response) => {
+                ctx.decode::<OwnedServerStreamResponse>(&message)?;
+                if ctx.index() == 2 {
+                    return Err(Status::resource_exhausted("too many responses"));
+                }
+            }
+            _ => {}
+        }
+        Ok(message)
+    };
+    let server = TestServer::new(Routes::new(
+        InspectLayer::new(server_inspector).layer(SampleServer::new(sample_mock())),
+    ));
+
+    // client side: record the bidi replies
+    let replies = Arc::new(Mutex::new(Vec::new()));
+    let client_inspector = {
+        let replies = replies.clone();
+        move |ctx: &InspectContext, message: Bytes| -> Result<Bytes, Status> {
+            if ctx.direction() == Direction::Response && ctx.method() == "bidi_stream" {
+                let reply = ctx.decode::<OwnedSampleReply>(&message)?;
+                replies
+                    .lock()
+                    .unwrap()
+                    .push(reply.get_ref().message().unwrap_or_default().to_string());
+            }
+            Ok(message)
+        }
+    };
+    let mut client = SampleClient::new(InspectService::new(server.channel(), client_inspector));
+
+    // client stream is rejected by the server
+    let request_stream = tokio_stream::iter((0..10).map(client_stream_request));
+    let Err(status) = client.client_stream(request_stream).await else {
+        panic!("client stream should be rejected");
+    };
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "chunk 3 rejected");
+
+    // server stream is cut after 2 messages
+    let mut response = client
+        .server_stream(server_stream_request(5))
+        .await
+        .unwrap()
+        .into_inner();
+    let mut count = 0;
+    let status = loop {
+        match response.next().await {
+            Some(Ok(_)) => count += 1,
+            Some(Err(status)) => break status,
+            None => panic!("stream should end with an error"),
+        }
+    };
+    assert_eq!(count, 2);
+    assert_eq!(status.code(), Code::ResourceExhausted);
+
+    // bidi stream passes through and is observed on the client
+    let request_stream = tokio_stream::iter(0..3).map(|i| sample_request(&format!("name {i}")));
+    let mut response = client
+        .bidi_stream(request_stream)
+        .await
+        .unwrap()
+        .into_inner();
+    while let Some(msg) = response.next().await {
+        msg.unwrap();
+    }
+    assert_eq!(
+        *replies.lock().unwrap(),
+        vec!["hello name 0", "hello name 1", "hello name 2"]
+    );
+
+    server.shutdown().await.unwrap();
+}
@@ -1,5 +1,8 @@
 pub mod generated;
 
+#[cfg(test)]
+mod fixtures;
+
 #[cfg(test)]
 mod reflect_test;
 
@@ -11,3 +14,45 @@ mod tonic_tests;
 
 #[cfg(test)]
 mod fb_tests;
+
+#[cfg(test)]
+mod dynamic_tests;
+
+#[cfg(test)]
+mod inspect_tests;
+
+#[cfg(test)]
+mod naming_tests;
+
+#[cfg(test)]
+mod compression_tests;
+
+#[cfg(test)]
+mod mock_tests;
+
+#[cfg(test)]
+mod testing_tests;
+
+#[cfg(test)]
+mod status_tests;
+
+#[cfg(test)]
+mod decode_error_tests;
+
+#[cfg(test)]
+mod compat_tests;
+
+#[cfg(test)]
+mod policy_tests;
+
+#[cfg(test)]
+mod generator_tests;
+
+#[cfg(test)]
+mod object_api_tests;
+
+#[cfg(test)]
+mod proxy_tests;
+
+#[cfg(doctest)]
+mod wrapper_tests;
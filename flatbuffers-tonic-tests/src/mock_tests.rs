@@ -0,0 +1,103 @@
+use tokio_stream::StreamExt;
+use tonic::{Code, Status};
+
+use crate::fixtures::{
+    client_stream_request, client_stream_response, hello_reply, hello_request, sample_reply,
+    sample_request, server_stream_request, server_stream_response,
+};
+use crate::generated::{greeter_mock::MockGreeter, sample_mock::MockSample};
+
+#[tokio::test]
+async fn test_mock_unary() {
+    let mock = MockGreeter::new();
+    mock.say_hello
+        .respond(hello_reply("hello mock"))
+        .respond_err(Status::not_found("no such user"))
+        .times(3);
+    let mut client = mock.clone().into_client();
+
+    let reply = client
+        .say_hello(hello_request("first"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello mock"));
+
+    let Err(status) = client.say_hello(hello_request("second")).await else {
+        panic!("expected the programmed error");
+    };
+    assert_eq!(status.code(), Code::NotFound);
+
+    // Nothing left to respond with.
+    let Err(status) = client.say_hello(hello_request("third")).await else {
+        panic!("expected unimplemented");
+    };
+    assert_eq!(status.code(), Code::Unimplemented);
+
+    mock.verify();
+    let names = mock
+        .say_hello
+        .take_requests()
+        .iter()
+        .map(|call| call[0].get_ref().name().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["first", "second", "third"]);
+}
+
+#[tokio::test]
+#[should_panic(expected = "expected 2 calls, got 1")]
+async fn test_mock_verify_fails() {
+    let mock = MockGreeter::new();
+    mock.say_hello.respond(hello_reply("once")).times(2);
+    let mut client = mock.clone().into_client();
+    client.say_hello(hello_request("only")).await.unwrap();
+    mock.verify();
+}
+
+#[tokio::test]
+async fn test_mock_streaming() {
+    let mock = MockSample::new();
+    mock.client_stream
+        .respond_with(|requests| Ok(vec![client_stream_response(requests.len() as i32)]));
+    mock.server_stream
+        .respond_stream([server_stream_response("one"), server_stream_response("two")]);
+    mock.bidi_stream.respond_with(|requests| {
+        Ok(requests
+            .iter()
+            .map(|r| sample_reply(&format!("echo {}", r.get_ref().name().unwrap())))
+            .collect())
+    });
+    let mut client = mock.clone().into_client();
+
+    let requests = tokio_stream::iter((0..3).map(client_stream_request));
+    let resp = client.client_stream(requests).await.unwrap().into_inner();
+    assert_eq!(resp.get_ref().count(), 3);
+
+    let mut stream = client
+        .server_stream(server_stream_request(2))
+        .await
+        .unwrap()
+        .into_inner();
+    let mut messages = Vec::new();
+    while let Some(resp) = stream.next().await {
+        messages.push(resp.unwrap().get_ref().message().unwrap().to_string());
+    }
+    assert_eq!(messages, ["one", "two"]);
+
+    let requests = tokio_stream::iter(["a", "b"].map(sample_request));
+    let mut stream = client.bidi_stream(requests).await.unwrap().into_inner();
+    let mut messages = Vec::new();
+    while let Some(resp) = stream.next().await {
+        messages.push(resp.unwrap().get_ref().message().unwrap().to_string());
+    }
+    assert_eq!(messages, ["echo a", "echo b"]);
+
+    assert_eq!(mock.client_stream.call_count(), 1);
+    assert_eq!(mock.client_stream.take_requests()[0].len(), 3);
+    assert_eq!(mock.server_stream.call_count(), 1);
+    assert_eq!(
+        mock.server_stream.take_requests()[0][0].get_ref().count(),
+        2
+    );
+    assert_eq!(mock.bidi_stream.call_count(), 1);
+}
@@ -0,0 +1,326 @@
+use flatbuffers_tonic::{FBBuilder, testing::connect};
+use tokio_stream::StreamExt;
+
+use crate::generated::{
+    self, OwnedLookupReply, OwnedLookupRequest, keywords_client::KeywordsClient,
+    keywords_server::KeywordsServer,
+};
+
+fn lookup_reply(value: &str) -> OwnedLookupReply {
+    let mut builder = FBBuilder::new();
+    let value = builder.get_mut().create_string(value);
+    let reply = generated::naming::lookup_reply::create(
+        builder.get_mut(),
+        &generated::naming::lookup_replyArgs { value: Some(value) },
+    );
+    builder.finish_owned(reply).into()
+}
+
+fn lookup_request(key: &str) -> OwnedLookupRequest {
+    let mut builder = FBBuilder::new();
+    let key = builder.get_mut().create_string(key);
+    let req = generated::naming::lookup_request::create(
+        builder.get_mut(),
+        &generated::naming::lookup_requestArgs { key: Some(key) },
+    );
+    builder.finish_owned(req).into()
+}
+
+fn key(request: &tonic::Request<OwnedLookupRequest>) -> String {
+    request
+        .get_ref()
+        .get_ref()
+        .key()
+        .unwrap_or_default()
+        .to_string()
+}
+
+pub struct KeywordsSvc {}
+
+// Methods named after rust keywords are raw identifiers, or get a trailing
+// underscore when they cannot be raw.
+#[tonic::async_trait]
+impl generated::keywords_server::Keywords for KeywordsSvc {
+    async fn r#type(
+        &self,
+        request: tonic::Request<OwnedLookupRequest>,
+    ) -> Result<tonic::Response<OwnedLookupReply>, tonic::Status> {
+        Ok(tonic::Response::new(lookup_reply(&format!(
+            "type {}",
+            key(&request)
+        ))))
+    }
+
+    type matchStream = std::pin::Pin<
+        Box<dyn tokio_stream::Stream<Item = Result<OwnedLookupReply, tonic::Status>> + Send>,
+    >;
+
+    async fn r#match(
+        &self,
+        request: tonic::Request<OwnedLookupRequest>,
+    ) -> Result<tonic::Response<Self::matchStream>, tonic::Status> {
+        let key = key(&request);
+        let replies = (0..2).map(move |i| Ok(lookup_reply(&format!("match {key} {i}"))));
+        Ok(tonic::Response::new(Box::pin(tokio_stream::iter(replies))))
+    }
+
+    async fn self_(
+        &self,
+        request: tonic::Request<OwnedLookupRequest>,
+    ) -> Result<tonic::Response<OwnedLookupReply>, tonic::Status> {
+        Ok(tonic::Response::new(lookup_reply(&format!(
+            "self {}",
+            key(&request)
+        ))))
+    }
+
+    async fn get_value(
+        &self,
+        request: tonic::Request<OwnedLookupRequest>,
+    ) -> Result<tonic::Response<OwnedLookupReply>, tonic::Status> {
+        Ok(tonic::Response::new(lookup_reply(&format!(
+            "value {}",
+            key(&request)
+        ))))
+    }
+}
+
+#[tokio::test]
+async fn test_keyword_methods() {
+    let (server, mut client) = connect(KeywordsServer::new(KeywordsSvc {}), KeywordsClient::new);
+    let value = |reply: OwnedLookupReply| reply.get_ref().value().unwrap_or_default().to_string();
+
+    let reply = client.r#type(lookup_request("a")).await.unwrap();
+    assert_eq!(value(reply.into_inner()), "type a");
+    let reply = client.self_(lookup_request("b")).await.unwrap();
+    assert_eq!(value(reply.into_inner()), "self b");
+    let reply = client.get_value(lookup_request("c")).await.unwrap();
+    assert_eq!(value(reply.into_inner()), "value c");
+
+    let mut stream = client
+        .r#match(lookup_request("d"))
+        .await
+        .unwrap()
+        .into_inner();
+    let mut values = Vec::new();
+    while let Some(reply) = stream.next().await {
+        values.push(value(reply.unwrap()));
+    }
+    assert_eq!(values, vec!["match d 0", "match d 1"]);
+
+    server.shutdown().await.unwrap();
+}
+
+#[test]
+fn test_namespace_rs() {
+    use flatbuffers_util::reflect::namespace_rs;
+    let cases = [
+        ("sample", "sample"),
+        ("fbs.helloworld", "fbs::helloworld"),
+        ("MyGame", "my_game"),
+        ("MyGame.Sample", "my_game::sample"),
+        // flatc splits digits from letters.
+        ("my_game.sample_v2", "my_game::sample_v_2"),
+        ("Company.MyGame.Sample2", "company::my_game::sample_2"),
+        ("HTTPServer.Api", "httpserver::api"),
+        ("Upper_Snake.Case", "upper_snake::case"),
+        ("type.match", "type_::match_"),
+    ];
+    for (namespace, expected) in cases {
+        assert_eq!(namespace_rs(namespace), expected, "{namespace}");
+    }
+}
+
+#[tokio::test]
+async fn test_camel_case_namespace() {
+    use crate::generated::{
+        OwnedGameReply, OwnedGameRequest, game_service_mock::MockGameService, my_game,
+    };
+
+    let mut builder = FBBuilder::new();
+    let score = my_game::sample::GameReply::create(
+        builder.get_mut(),
+        &my_game::sample::GameReplyArgs { score: 7 },
+    );
+    let reply: OwnedGameReply = builder.finish_owned(score).into();
+    let mock = MockGameService::new();
+    mock.join.respond(reply);
+
+    let mut builder = FBBuilder::new();
+    let player = builder.get_mut().create_string("p1");
+    let req = my_game::sample::GameRequest::create(
+        builder.get_mut(),
+        &my_game::sample::GameRequestArgs {
+            player: Some(player),
+        },
+    );
+    let request: OwnedGameRequest = builder.finish_owned(req).into();
+    let reply = mock.clone().into_client().join(request).await.unwrap();
+    assert_eq!(reply.into_inner().get_ref().score(), 7);
+}
+
+#[tokio::test]
+async fn test_namespace_across_files() {
+    use crate::generated::acme_greeter::{
+        OwnedFarewellReply, OwnedFarewellRequest, OwnedGreetReply, OwnedGreetRequest, acme,
+        farewell_mock::MockFarewell, greeter_mock::MockGreeter,
+    };
+
+    // Tables of both files are in the same rust module.
+    let mut builder = FBBuilder::new();
+    let message = builder.get_mut().create_string("hi");
+    let reply = acme::greeter::GreetReply::create(
+        builder.get_mut(),
+        &acme::greeter::GreetReplyArgs {
+            message: Some(message),
+        },
+    );
+    let greet_reply: OwnedGreetReply = builder.finish_owned(reply).into();
+    let mut builder = FBBuilder::new();
+    let message = builder.get_mut().create_string("bye");
+    let reply = acme::greeter::FarewellReply::create(
+        builder.get_mut(),
+        &acme::greeter::FarewellReplyArgs {
+            message: Some(message),
+        },
+    );
+    let farewell_reply: OwnedFarewellReply = builder.finish_owned(reply).into();
+
+    let greeter = MockGreeter::new();
+    greeter.greet.respond(greet_reply);
+    assert_eq!(greeter.greet.path(), "/acme.greeter.Greeter/Greet");
+    let farewell = MockFarewell::new();
+    farewell.leave.respond(farewell_reply);
+    assert_eq!(farewell.leave.path(), "/acme.greeter.Farewell/Leave");
+
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string("a");
+    let req = acme::greeter::GreetRequest::create(
+        builder.get_mut(),
+        &acme::greeter::GreetRequestArgs { name: Some(name) },
+    );
+    let request: OwnedGreetRequest = builder.finish_owned(req).into();
+    let reply = greeter.clone().into_client().greet(request).await.unwrap();
+    assert_eq!(reply.into_inner().get_ref().message(), Some("hi"));
+
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string("a");
+    let req = acme::greeter::FarewellRequest::create(
+        builder.get_mut(),
+        &acme::greeter::FarewellRequestArgs { name: Some(name) },
+    );
+    let request: OwnedFarewellRequest = builder.finish_owned(req).into();
+    let reply = farewell.clone().into_client().leave(request).await.unwrap();
+    assert_eq!(reply.into_inner().get_ref().message(), Some("bye"));
+}
+
+#[tokio::test]
+async fn test_no_namespace_routes() {
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use flatbuffers_tonic::{InspectContext, InspectService, testing::in_process_channel};
+    use tonic::{Status, service::Routes};
+
+    use crate::generated::{plain, vendor};
+
+    let paths = Arc::new(Mutex::new(Vec::new()));
+    let record = {
+        let paths = paths.clone();
+        move |ctx: &InspectContext, message: Bytes| -> Result<Bytes, Status> {
+            paths.lock().unwrap().push(ctx.path().to_string());
+            Ok(message)
+        }
+    };
+
+    let mut builder = FBBuilder::new();
+    let text = builder.get_mut().create_string("x");
+    let reply = plain::PlainReply::create(
+        builder.get_mut(),
+        &plain::PlainReplyArgs { text: Some(text) },
+    );
+    let reply: plain::OwnedPlainReply = builder.finish_owned(reply).into();
+    let mock = plain::plain_mock::MockPlain::new();
+    mock.echo.respond(reply);
+    let channel = in_process_channel(Routes::new(plain::plain_server::PlainServer::new(
+        mock.clone(),
+    )));
+    let mut client =
+        plain::plain_client::PlainClient::new(InspectService::new(channel, record.clone()));
+    let mut builder = FBBuilder::new();
+    let text = builder.get_mut().create_string("x");
+    let req = plain::PlainRequest::create(
+        builder.get_mut(),
+        &plain::PlainRequestArgs { text: Some(text) },
+    );
+    let request: plain::OwnedPlainRequest = builder.finish_owned(req).into();
+    let reply = client.echo(request).await.unwrap().into_inner();
+    assert_eq!(reply.get_ref().text(), Some("x"));
+
+    let mut builder = FBBuilder::new();
+    let text = builder.get_mut().create_string("y");
+    let reply = vendor::VendorReply::create(
+        builder.get_mut(),
+        &vendor::VendorReplyArgs { text: Some(text) },
+    );
+    let reply: vendor::OwnedVendorReply = builder.finish_owned(reply).into();
+    let mock = vendor::vendor_mock::MockVendor::new();
+    mock.echo.respond(reply);
+    let channel = in_process_channel(Routes::new(vendor::vendor_server::VendorServer::new(
+        mock.clone(),
+    )));
+    let mut client = vendor::vendor_client::VendorClient::new(InspectService::new(channel, record));
+    let mut builder = FBBuilder::new();
+    let text = builder.get_mut().create_string("y");
+    let req = vendor::VendorRequest::create(
+        builder.get_mut(),
+        &vendor::VendorRequestArgs { text: Some(text) },
+    );
+    let request: vendor::OwnedVendorRequest = builder.finish_owned(req).into();
+    let reply = client.echo(request).await.unwrap().into_inner();
+    assert_eq!(reply.get_ref().text(), Some("y"));
+
+    assert_eq!(
+        *paths.lock().unwrap(),
+        [
+            "/Plain/Echo",
+            "/Plain/Echo",
+            "/vendor.Vendor/Echo",
+            "/vendor.Vendor/Echo"
+        ]
+    );
+    assert_eq!(
+        vendor::vendor_attributes::method("/vendor.Vendor/Echo"),
+        Some(&[][..])
+    );
+}
+
+// Tables of other namespaces sharing a name with a table of the file get the namespace
+// in their wrapper name.
+#[tokio::test]
+async fn test_wrappers_of_tables_sharing_a_name() {
+    use generated::versions::{OwnedRequest, OwnedV1Request, upgrade_mock::MockUpgrade, v_1, v_2};
+
+    let mut builder = FBBuilder::new();
+    let id = v_2::Request::create(builder.get_mut(), &v_2::RequestArgs { name: None, id: 7 });
+    let reply: OwnedRequest = builder.finish_owned(id).into();
+    let mock = MockUpgrade::new();
+    mock.migrate.respond(reply);
+
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string("old");
+    let request = v_1::Request::create(builder.get_mut(), &v_1::RequestArgs { name: Some(name) });
+    let request: OwnedV1Request = builder.finish_owned(request).into();
+    let reply = mock
+        .clone()
+        .into_client()
+        .migrate(request)
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().id(), 7);
+    assert_eq!(
+        mock.migrate.take_requests()[0][0].get_ref().name(),
+        Some("old")
+    );
+}
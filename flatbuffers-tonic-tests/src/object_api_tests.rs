@@ -0,0 +1,118 @@
+use flatbuffers_tonic::{OwnedFBCodecable, testing::connect};
+use tonic::{Request, Response, Status};
+
+use crate::generated::objects::{
+    OwnedArea, OwnedDrawing, OwnedShape,
+    geometry_client::GeometryClient,
+    geometry_server::{Geometry, GeometryServer},
+    objects::{AreaT, DrawingT, Point, PointT, Shape, ShapeT},
+};
+
+struct GeometrySvc;
+
+#[tonic::async_trait]
+impl Geometry for GeometrySvc {
+    async fn measure(&self, request: Request<OwnedShape>) -> Result<Response<OwnedArea>, Status> {
+        let shape = request.into_inner().unpack();
+        let points = shape.points.unwrap_or_default();
+        // Shoelace formula.
+        let twice: f32 = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum();
+        let area = AreaT {
+            value: f64::from(twice.abs() / 2.0),
+        };
+        Ok(Response::new(OwnedArea::from(&area)))
+    }
+}
+
+fn square(side: f32) -> ShapeT {
+    let corners = [(0.0, 0.0), (side, 0.0), (side, side), (0.0, side)];
+    ShapeT {
+        name: Some("square".to_string()),
+        points: Some(corners.iter().map(|&(x, y)| PointT { x, y }).collect()),
+    }
+}
+
+#[test]
+fn test_object_api_round_trip() {
+    let shape = square(2.0);
+    let owned = OwnedShape::from(&shape);
+    assert_eq!(owned.get_ref().name(), Some("square"));
+    assert_eq!(
+        owned.get_ref().points().unwrap().get(2),
+        &Point::new(2.0, 2.0)
+    );
+    assert_eq!(owned.unpack(), shape);
+    // --gen-name-strings passed through to flatc.
+    assert_eq!(Shape::get_fully_qualified_name(), "objects.Shape");
+}
+
+#[tokio::test]
+async fn test_object_api_service() {
+    let (server, mut client) = connect(GeometryServer::new(GeometrySvc), GeometryClient::new);
+    let area = client
+        .measure(OwnedShape::from(&square(3.0)))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(area.unpack(), AreaT { value: 9.0 });
+    server.shutdown().await.unwrap();
+}
+
+#[test]
+fn test_wrapper_of_nested_table() {
+    let drawing = DrawingT {
+        shapes: Some(vec![square(1.0), square(2.0)]),
+    };
+    let bytes = OwnedDrawing::from(&drawing).into_bytes();
+    let owned = OwnedDrawing::new_from_bytes(bytes).unwrap();
+    assert_eq!(owned.get_ref().shapes().unwrap().len(), 2);
+    assert_eq!(owned.unpack(), drawing);
+}
+
+#[test]
+fn test_project_nested_table() {
+    let drawing = DrawingT {
+        shapes: Some(vec![square(1.0), square(2.0)]),
+    };
+    let owned = OwnedDrawing::from(&drawing);
+    let shape = owned
+        .0
+        .project(|root| root.shapes().map(|shapes| shapes.get(1)))
+        .unwrap()
+        .map(OwnedShape::from)
+        .unwrap();
+    assert_eq!(shape.unpack(), square(2.0));
+
+    // The projection shares the buffer of the drawing.
+    let parent = owned.0.get_slice().as_ptr_range();
+    assert!(parent.contains(&shape.0.get_slice().as_ptr()));
+
+    // And is a flatbuffer of its own on the wire.
+    let shape = OwnedShape::new_from_bytes(shape.into_bytes()).unwrap();
+    assert_eq!(shape.get_ref().name(), Some("square"));
+
+    let empty = OwnedDrawing::from(&DrawingT { shapes: None });
+    let none = empty
+        .0
+        .project(|root| root.shapes().map(|shapes| shapes.get(0)))
+        .unwrap();
+    assert!(none.is_none());
+
+    // A table of another buffer is an error.
+    let other = owned
+        .0
+        .project(|_| empty.get_ref().shapes().map(|shapes| shapes.get(0)))
+        .unwrap();
+    assert!(other.is_none());
+    let err = empty
+        .0
+        .project(|_| owned.get_ref().shapes().map(|shapes| shapes.get(0)));
+    assert!(matches!(
+        err,
+        Err(flatbuffers::InvalidFlatbuffer::RangeOutOfBounds { .. })
+    ));
+}
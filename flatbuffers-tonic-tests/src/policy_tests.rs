@@ -0,0 +1,259 @@
+use std::{
+    future::pending,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use flatbuffers_tonic::{
+    FBBuilder,
+    testing::{MockStream, in_process_channel},
+};
+use tonic::{Code, Request, Response, Status, service::Routes};
+
+use crate::generated::policy::{
+    OwnedCallReply, OwnedCallRequest, calls_attributes,
+    calls_client::CallsClient,
+    calls_mock::MockCalls,
+    calls_policy::{self, CallsPolicyClient},
+    calls_server::{Calls, CallsServer},
+    policy::{call_reply, call_replyArgs, call_request, call_requestArgs},
+};
+
+fn call_request(name: &str) -> OwnedCallRequest {
+    let mut builder = FBBuilder::new();
+    let name = builder.get_mut().create_string(name);
+    let req = call_request::create(builder.get_mut(), &call_requestArgs { name: Some(name) });
+    builder.finish_owned(req).into()
+}
+
+fn call_reply(message: &str) -> OwnedCallReply {
+    let mut builder = FBBuilder::new();
+    let message = builder.get_mut().create_string(message);
+    let reply = call_reply::create(
+        builder.get_mut(),
+        &call_replyArgs {
+            message: Some(message),
+        },
+    );
+    builder.finish_owned(reply).into()
+}
+
+#[test]
+fn test_policies_from_attributes() {
+    assert_eq!(
+        calls_policy::DEADLINE.timeout,
+        Some(Duration::from_millis(500))
+    );
+    assert_eq!(calls_policy::DEADLINE.retries, 0);
+    assert_eq!(calls_policy::RETRIED.timeout, Some(Duration::from_secs(1)));
+    assert_eq!(calls_policy::RETRIED.retries, 2);
+    assert_eq!(
+        calls_policy::LISTEN.timeout,
+        Some(Duration::from_millis(500))
+    );
+    assert_eq!(
+        calls_policy::PLAIN,
+        flatbuffers_tonic::CallPolicy::default()
+    );
+    assert!(calls_attributes::RETRIED.contains(&("idempotent", "0")));
+}
+
+#[tokio::test]
+async fn test_retry_idempotent() {
+    let mock = MockCalls::new();
+    mock.retried
+        .respond_err(Status::unavailable("down"))
+        .respond_err(Status::unavailable("still down"))
+        .respond(call_reply("up"));
+    let mut client = CallsPolicyClient::new(mock.clone().into_client());
+    let reply = client
+        .retried(call_request("a"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("up"));
+    assert_eq!(mock.retried.call_count(), 3);
+    // Every attempt sent the same request.
+    for requests in mock.retried.take_requests() {
+        assert_eq!(requests[0].get_ref().name(), Some("a"));
+    }
+
+    // Gives up after the retries, and does not retry other errors.
+    mock.retried
+        .respond_err(Status::unavailable("1"))
+        .respond_err(Status::unavailable("2"))
+        .respond_err(Status::unavailable("3"))
+        .respond_err(Status::invalid_argument("bad"));
+    let Err(status) = client.retried(call_request("b")).await else {
+        panic!("expected the retries to run out");
+    };
+    assert_eq!(status.message(), "3");
+    let Err(status) = client.retried(call_request("c")).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(mock.retried.call_count(), 7);
+
+    // Methods that are not idempotent are not retried.
+    mock.deadline.respond_err(Status::unavailable("down"));
+    let Err(status) = client.deadline(call_request("d")).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(mock.deadline.call_count(), 1);
+}
+
+#[tokio::test]
+async fn test_timeout_applied() {
+    let mock = MockCalls::new();
+    mock.deadline.respond_with(|_| Ok(vec![call_reply("ok")]));
+    mock.plain.respond_with(|_| Ok(vec![call_reply("ok")]));
+    let timeouts = Arc::new(Mutex::new(Vec::new()));
+    let record = {
+        let timeouts = timeouts.clone();
+        move |request: Request<()>| {
+            let timeout = request
+                .metadata()
+                .get("grpc-timeout")
+                .map(|v| v.to_str().unwrap().to_string());
+            timeouts.lock().unwrap().push(timeout);
+            Ok(request)
+        }
+    };
+    let channel = in_process_channel(Routes::new(CallsServer::new(mock.clone())));
+    let mut client = CallsPolicyClient::new(CallsClient::with_interceptor(channel, record));
+    client.deadline(call_request("a")).await.unwrap();
+
+    // A timeout set by the caller wins.
+    let mut request = Request::new(call_request("b"));
+    request.set_timeout(Duration::from_secs(7));
+    client.deadline(request).await.unwrap();
+
+    // Methods without a timeout attribute send none.
+    client.plain(call_request("c")).await.unwrap();
+
+    let timeouts = timeouts.lock().unwrap();
+    assert_eq!(
+        *timeouts,
+        [
+            Some("500000u".to_string()),
+            Some("7000000u".to_string()),
+            None
+        ]
+    );
+}
+
+/// Never replies, and answers `retried` with `Unavailable` only after a while.
+#[derive(Default)]
+struct StalledCalls {
+    retried: AtomicUsize,
+}
+
+#[tonic::async_trait]
+impl Calls for StalledCalls {
+    async fn deadline(
+        &self,
+        _request: Request<OwnedCallRequest>,
+    ) -> Result<Response<OwnedCallReply>, Status> {
+        pending().await
+    }
+
+    async fn retried(
+        &self,
+        _request: Request<OwnedCallRequest>,
+    ) -> Result<Response<OwnedCallReply>, Status> {
+        self.retried.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        Err(Status::unavailable("busy"))
+    }
+
+    type listenStream = MockStream<OwnedCallReply>;
+
+    async fn listen(
+        &self,
+        _request: Request<OwnedCallRequest>,
+    ) -> Result<Response<Self::listenStream>, Status> {
+        pending().await
+    }
+
+    async fn plain(
+        &self,
+        _request: Request<OwnedCallRequest>,
+    ) -> Result<Response<OwnedCallReply>, Status> {
+        pending().await
+    }
+}
+
+// The server enforces grpc-timeout itself, so it is dropped on the way to check that
+// the client gives up on its own.
+fn strip_timeout(mut request: Request<()>) -> Result<Request<()>, Status> {
+    request.metadata_mut().remove("grpc-timeout");
+    Ok(request)
+}
+
+#[tokio::test]
+async fn test_deadline_without_reply() {
+    let service = Arc::new(StalledCalls::default());
+    let channel = in_process_channel(Routes::new(CallsServer::from_arc(service.clone())));
+    let mut client = CallsPolicyClient::new(CallsClient::with_interceptor(channel, strip_timeout));
+
+    let start = Instant::now();
+    let Err(status) = client.deadline(call_request("a")).await else {
+        panic!("expected the deadline to pass");
+    };
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(500), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(900), "{elapsed:?}");
+
+    // Server streaming calls time out while waiting for the response.
+    let start = Instant::now();
+    let Err(status) = client.listen(call_request("b")).await else {
+        panic!("expected the deadline to pass");
+    };
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    assert!(start.elapsed() < Duration::from_millis(900));
+
+    // A caller timeout is enforced the same way.
+    let mut request = Request::new(call_request("c"));
+    request.set_timeout(Duration::from_millis(100));
+    let start = Instant::now();
+    let Err(status) = client.deadline(request).await else {
+        panic!("expected the deadline to pass");
+    };
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    assert!(start.elapsed() < Duration::from_millis(400));
+}
+
+#[tokio::test]
+async fn test_retries_within_deadline() {
+    let service = Arc::new(StalledCalls::default());
+    let channel = in_process_channel(Routes::new(CallsServer::from_arc(service.clone())));
+    let mut client = CallsPolicyClient::new(CallsClient::with_interceptor(channel, strip_timeout));
+
+    // Attempts of 300ms and their backoff do not fit the 1s deadline, the last attempt
+    // is cut short instead of running past it.
+    let start = Instant::now();
+    let Err(status) = client.retried(call_request("a")).await else {
+        panic!("expected the deadline to pass");
+    };
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_secs(1), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(1400), "{elapsed:?}");
+    assert_eq!(service.retried.load(Ordering::SeqCst), 3);
+
+    // No retry starts when its backoff alone would pass the deadline.
+    let mut request = Request::new(call_request("b"));
+    request.set_timeout(Duration::from_millis(340));
+    let start = Instant::now();
+    let Err(status) = client.retried(request).await else {
+        panic!("expected the deadline to pass");
+    };
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    assert!(start.elapsed() < Duration::from_millis(340));
+    assert_eq!(service.retried.load(Ordering::SeqCst), 4);
+}
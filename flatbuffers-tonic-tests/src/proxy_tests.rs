@@ -0,0 +1,249 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use flatbuffers_tonic::{
+    CompressionEncoding, GrpcProxy, RawFlatBuffers, RawFlatBuffersCodec,
+    testing::in_process_channel,
+};
+use tokio_stream::StreamExt;
+use tonic::{Code, Status, service::Routes, transport::Channel};
+
+use crate::fixtures::{hello_reply, hello_request, sample_mock, sample_reply, sample_request};
+use crate::generated::{
+    OwnedHelloRequest, greeter_attributes, greeter_client::GreeterClient,
+    greeter_mock::MockGreeter, greeter_server::GreeterServer,
+    hello_sample_client::HelloSampleClient, hello_sample_mock::MockHelloSample,
+    hello_sample_server::HelloSampleServer, sample_attributes, sample_client::SampleClient,
+    sample_mock::MockSample, sample_server::SampleServer,
+};
+
+#[test]
+fn test_service_descriptor() {
+    let descriptor = &sample_attributes::DESCRIPTOR;
+    assert_eq!(descriptor.name, "sample.Sample");
+    let method = descriptor.method("/sample.Sample/bidi_stream").unwrap();
+    assert_eq!(method.name, "bidi_stream");
+    assert_eq!(method.request, "sample.sample_request");
+    assert_eq!(method.response, "sample.sample_reply");
+    assert!(method.client_streaming && method.server_streaming);
+    assert!(descriptor.method("/sample.Sample/missing").is_none());
+
+    let descriptor = &greeter_attributes::DESCRIPTOR;
+    assert_eq!(descriptor.name, "fbs.helloworld.Greeter");
+    assert_eq!(
+        descriptor.methods[0].path,
+        "/fbs.helloworld.Greeter/SayHello"
+    );
+}
+
+#[test]
+fn test_raw_decode_on_demand() {
+    let raw = RawFlatBuffers::new(hello_request("raw").0.into_bytes());
+    let request: OwnedHelloRequest = raw.decode().unwrap();
+    assert_eq!(request.get_ref().name(), Some("raw"));
+    // Zero copy, the decoded message shares the buffer.
+    assert_eq!(request.0.get_slice().as_ptr(), raw.as_bytes().as_ptr());
+
+    // Not verified until decoded.
+    let raw = RawFlatBuffers::from(Bytes::from_static(&[1, 2, 3]));
+    assert!(raw.decode::<OwnedHelloRequest>().is_err());
+}
+
+#[tokio::test]
+async fn test_proxy_routes_by_path() {
+    let greeter = MockGreeter::new();
+    greeter
+        .say_hello
+        .respond(hello_reply("hello"))
+        .respond_err(Status::not_found("no such user"));
+    let tenants = Arc::new(Mutex::new(Vec::new()));
+    let record = {
+        let tenants = tenants.clone();
+        move |request: tonic::Request<()>| {
+            let tenant = request.metadata().get("x-tenant").cloned();
+            tenants.lock().unwrap().push(tenant);
+            Ok(request)
+        }
+    };
+    let greeter_channel = in_process_channel(Routes::new(GreeterServer::with_interceptor(
+        greeter.clone(),
+        record,
+    )));
+    let sample = MockSample::new();
+    sample.bidi_stream.respond_with(|requests| {
+        Ok(requests
+            .iter()
+            .map(|r| sample_reply(&format!("echo {}", r.get_ref().name().unwrap())))
+            .collect())
+    });
+    let sample_channel = in_process_channel(Routes::new(SampleServer::new(sample.clone())));
+
+    let proxy = GrpcProxy::new()
+        .route(&greeter_attributes::DESCRIPTOR, greeter_channel)
+        .route(&sample_attributes::DESCRIPTOR, sample_channel);
+    assert_eq!(proxy.paths().count(), 4);
+    let channel = in_process_channel(proxy.into_routes());
+
+    let mut client = GreeterClient::new(channel.clone());
+    let mut request = tonic::Request::new(hello_request("a"));
+    request
+        .metadata_mut()
+        .insert("x-tenant", "blue".parse().unwrap());
+    let reply = client.say_hello(request).await.unwrap().into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello"));
+    assert_eq!(
+        greeter.say_hello.take_requests()[0][0].get_ref().name(),
+        Some("a")
+    );
+    assert_eq!(*tenants.lock().unwrap(), [Some("blue".parse().unwrap())]);
+
+    // The upstream status reaches the client.
+    let Err(status) = client.say_hello(hello_request("b")).await else {
+        panic!("expected the upstream error");
+    };
+    assert_eq!(status.code(), Code::NotFound);
+    assert_eq!(status.message(), "no such user");
+
+    let mut client = SampleClient::new(channel.clone());
+    let requests = tokio_stream::iter(["x", "y"].map(sample_request));
+    let mut stream = client.bidi_stream(requests).await.unwrap().into_inner();
+    let mut messages = Vec::new();
+    while let Some(resp) = stream.next().await {
+        messages.push(resp.unwrap().get_ref().message().unwrap().to_string());
+    }
+    assert_eq!(messages, ["echo x", "echo y"]);
+
+    // Services without a route.
+    let mut client = HelloSampleClient::new(channel);
+    let Err(status) = client.say_hello(sample_request("c")).await else {
+        panic!("expected unimplemented");
+    };
+    assert_eq!(status.code(), Code::Unimplemented);
+}
+
+#[tokio::test]
+async fn test_proxy_forwards_without_verifying() {
+    let greeter = MockGreeter::new();
+    let upstream = in_process_channel(Routes::new(GreeterServer::new(greeter.clone())));
+    let channel = in_process_channel(
+        GrpcProxy::new()
+            .route(&greeter_attributes::DESCRIPTOR, upstream)
+            .into_routes(),
+    );
+
+    // The upstream rejects the malformed request, not the proxy.
+    let mut client = tonic::client::Grpc::new(channel);
+    client.ready().await.unwrap();
+    let path =
+        tonic::codegen::http::uri::PathAndQuery::from_static("/fbs.helloworld.Greeter/SayHello");
+    let request = tonic::Request::new(RawFlatBuffers::new(Bytes::from_static(&[1, 2, 3])));
+    let Err(status) = client
+        .unary::<_, RawFlatBuffers, _>(request, path, RawFlatBuffersCodec::default())
+        .await
+    else {
+        panic!("expected the upstream to reject the request");
+    };
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(greeter.say_hello.call_count(), 0);
+}
+
+#[tokio::test]
+async fn test_proxy_fallback_of_local_services() {
+    let greeter = MockGreeter::new();
+    greeter.say_hello.respond(hello_reply("remote"));
+    let upstream = in_process_channel(Routes::new(GreeterServer::new(greeter)));
+    let local = MockHelloSample::new();
+    local.say_hello.respond(sample_reply("local"));
+
+    let routes = GrpcProxy::<Channel>::new()
+        .route(&greeter_attributes::DESCRIPTOR, upstream)
+        .fallback_of(Routes::new(HelloSampleServer::new(local)));
+    let channel = in_process_channel(routes);
+
+    let reply = HelloSampleClient::new(channel.clone())
+        .say_hello(sample_request("a"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("local"));
+    let reply = GreeterClient::new(channel)
+        .say_hello(hello_request("b"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("remote"));
+}
+
+#[tokio::test]
+async fn test_proxy_passes_compressed_and_large_messages() {
+    let encoding = CompressionEncoding::Gzip;
+    let greeter = MockGreeter::new();
+    greeter.say_hello.respond(hello_reply("large"));
+    let greeter_channel = in_process_channel(Routes::new(
+        GreeterServer::new(greeter.clone()).max_decoding_message_size(8 * 1024 * 1024),
+    ));
+    let sample_channel = in_process_channel(Routes::new(
+        SampleServer::new(sample_mock())
+            .accept_compressed(encoding)
+            .send_compressed(encoding),
+    ));
+    let channel = in_process_channel(
+        GrpcProxy::new()
+            .route(&greeter_attributes::DESCRIPTOR, greeter_channel)
+            .route(&sample_attributes::DESCRIPTOR, sample_channel)
+            .into_routes(),
+    );
+
+    // The compressed messages are forwarded as is, the proxy does not decompress them.
+    let mut client = SampleClient::new(channel.clone())
+        .send_compressed(encoding)
+        .accept_compressed(encoding);
+    let requests = tokio_stream::iter(["x", "y"].map(sample_request));
+    let mut stream = client.bidi_stream(requests).await.unwrap().into_inner();
+    let mut messages = Vec::new();
+    while let Some(resp) = stream.next().await {
+        messages.push(resp.unwrap().get_ref().message().unwrap().to_string());
+    }
+    assert_eq!(messages, ["hello x", "hello y"]);
+
+    // Only the upstream limits the message size.
+    let large = "x".repeat(5 * 1024 * 1024);
+    let reply = GreeterClient::new(channel)
+        .say_hello(hello_request(&large))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("large"));
+    assert_eq!(
+        greeter.say_hello.take_requests()[0][0].get_ref().name(),
+        Some(large.as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_proxy_resets_failed_request_stream() {
+    let sample = sample_mock();
+    let upstream = in_process_channel(Routes::new(SampleServer::new(sample.clone())));
+    let channel = in_process_channel(
+        GrpcProxy::new()
+            .route(&sample_attributes::DESCRIPTOR, upstream)
+            .into_routes(),
+    );
+
+    // The second message fails to encode on the client, which resets its request stream.
+    let requests =
+        tokio_stream::iter(["a".to_string(), "x".repeat(100)].map(|n| sample_request(&n)));
+    let mut client = SampleClient::new(channel).max_encoding_message_size(64);
+    let call = tokio::time::timeout(Duration::from_secs(1), client.bidi_stream(requests)).await;
+    if let Ok(Ok(response)) = call {
+        let mut stream = response.into_inner();
+        while let Some(Ok(_)) = stream.next().await {}
+    }
+
+    // The upstream sees the stream fail rather than end, and never answers the partial request.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(sample.bidi_stream.call_count(), 0);
+}
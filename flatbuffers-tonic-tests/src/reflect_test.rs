@@ -14,7 +14,7 @@ fn test_reflect_hello() {
         });
     });
 
-    let ctx = flatbuffers_util::reflect::GeneratorContext::parse_from_schema(&schema);
+    let ctx = flatbuffers_util::reflect::GeneratorContext::parse_from_schema(&schema).unwrap();
     let services = ctx.get_services();
     assert_eq!(services.len(), 1);
     let service = &services[0];
@@ -52,7 +52,7 @@ fn test_reflect_sample() {
         });
     });
 
-    let ctx = flatbuffers_util::reflect::GeneratorContext::parse_from_schema(&schema);
+    let ctx = flatbuffers_util::reflect::GeneratorContext::parse_from_schema(&schema).unwrap();
     let services = ctx.get_services();
     assert_eq!(services.len(), 2);
     let service = &services[1];
@@ -68,3 +68,172 @@ fn test_reflect_sample() {
         }
     }
 }
+
+#[test]
+fn test_reflect_attributes() {
+    let schema = flatbuffers_util::reflect::compile_reflection_schema(std::path::Path::new(
+        "../fbs/sample.fbs",
+    ));
+    let ctx =
+        flatbuffers_util::reflect::GeneratorContext::parse_from_schema(&schema.get_ref()).unwrap();
+    let service = &ctx.get_services()[0];
+    assert_eq!(service.name, "HelloSample");
+    assert_eq!(service.documentation, [" Greets in unary calls."]);
+    let types = ctx.collect_in_out_types();
+    assert_eq!(types[0].fb_type, "sample_request");
+    assert_eq!(
+        ctx.documentation(&types[0]),
+        [" Request of the HelloSample methods."]
+    );
+    assert!(ctx.documentation(&types[1]).is_empty());
+    let method = &service.methods[1];
+    assert_eq!(method.name, "say_hello2");
+    let attributes: Vec<_> = method
+        .attributes
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(attributes, [("error", "sample_error")]);
+    assert_eq!(
+        method.documentation,
+        [" Like say_hello, but fails with sample_error details."]
+    );
+
+    use crate::generated::hello_sample_attributes;
+    assert_eq!(hello_sample_attributes::SAY_HELLO2, attributes);
+    assert_eq!(
+        hello_sample_attributes::method("/sample.HelloSample/say_hello"),
+        Some(&[("streaming", "none")][..])
+    );
+    assert_eq!(
+        hello_sample_attributes::method("/sample.Sample/say_hello"),
+        None
+    );
+}
+
+#[test]
+fn test_reflect_model() {
+    use flatbuffers_util::reflect::{FieldType, reflection::reflection::BaseType};
+
+    let schema = flatbuffers_util::reflect::compile_reflection_schema(std::path::Path::new(
+        "../fbs/dynamic.fbs",
+    ));
+    let ctx =
+        flatbuffers_util::reflect::GeneratorContext::parse_from_schema(&schema.get_ref()).unwrap();
+
+    let monster = ctx.object("dynamic.Monster").unwrap();
+    assert!(!monster.is_struct);
+    assert_eq!(monster.short_name(), "Monster");
+    assert_eq!(monster.namespace(), Some("dynamic"));
+    assert_eq!(monster.declaration_file.as_deref(), Some("dynamic.fbs"));
+    // Fields are in id order, the union adds a hidden type field.
+    let names: Vec<_> = monster.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "pos",
+            "mana",
+            "hp",
+            "name",
+            "friendly",
+            "inventory",
+            "color",
+            "weapons",
+            "equipped_type",
+            "equipped",
+            "path",
+            "tags",
+            "score"
+        ]
+    );
+    let mana = monster.field("mana").unwrap();
+    assert_eq!(mana.id, 1);
+    assert_eq!(mana.field_type, FieldType::Scalar(BaseType::Short));
+    assert_eq!(mana.default_integer, 150);
+    assert!(monster.field("name").unwrap().required);
+    assert!(!mana.required);
+    let color = monster.field("color").unwrap();
+    assert_eq!(color.default_integer, 2);
+    assert_eq!(
+        color.field_type,
+        FieldType::Enum {
+            name: "dynamic.Color".to_string(),
+            underlying: BaseType::Byte,
+        }
+    );
+    let types: Vec<_> = monster
+        .fields
+        .iter()
+        .map(|f| f.field_type.to_string())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "dynamic.Vec3",
+            "short",
+            "short",
+            "string",
+            "bool",
+            "[ubyte]",
+            "dynamic.Color",
+            "[dynamic.Weapon]",
+            "dynamic.Equipment",
+            "dynamic.Equipment",
+            "[dynamic.Vec3]",
+            "[string]",
+            "long"
+        ]
+    );
+
+    let vec3 = ctx.object("dynamic.Vec3").unwrap();
+    assert!(vec3.is_struct);
+    assert_eq!(vec3.bytesize, 12);
+    assert_eq!(vec3.fields[2].offset, 8);
+
+    let equipment = ctx.enum_def("dynamic.Equipment").unwrap();
+    assert!(equipment.is_union);
+    let variants: Vec<_> = equipment
+        .values
+        .iter()
+        .map(|v| (v.name.as_str(), v.union_type.as_deref()))
+        .collect();
+    assert_eq!(
+        variants,
+        [
+            ("NONE", None),
+            ("Weapon", Some("dynamic.Weapon")),
+            ("Armor", Some("dynamic.Armor"))
+        ]
+    );
+    let color = ctx.enum_def("dynamic.Color").unwrap();
+    assert!(!color.is_union);
+    assert_eq!(color.underlying, BaseType::Byte);
+    let values: Vec<_> = color
+        .values
+        .iter()
+        .map(|v| (v.name.as_str(), v.value))
+        .collect();
+    assert_eq!(values, [("Red", 0), ("Green", 1), ("Blue", 2)]);
+}
+
+#[test]
+fn test_reflect_same_short_name() {
+    let schema = flatbuffers_util::reflect::compile_reflection_schema(std::path::Path::new(
+        "../fbs/versions.fbs",
+    ));
+    let ctx =
+        flatbuffers_util::reflect::GeneratorContext::parse_from_schema(&schema.get_ref()).unwrap();
+    let types = ctx.collect_in_out_types();
+    let names: Vec<_> = types.iter().map(|t| t.full_name.as_str()).collect();
+    assert_eq!(names, ["v1.Request", "v2.Request"]);
+    assert_eq!(types[0].fb_type, "Request");
+    assert_eq!(types[0].namespace.as_deref(), Some("v1"));
+
+    let v2 = ctx.object("v2.Request").unwrap();
+    assert_eq!(v2.field("id").unwrap().id, 1);
+    assert_eq!(
+        v2.field("id").unwrap().documentation,
+        [" Replaces the name."]
+    );
+    assert!(ctx.object("v1.Request").unwrap().field("id").is_none());
+}
@@ -0,0 +1,51 @@
+use flatbuffers_tonic::{DETAILS_KEY, StatusExt};
+use tonic::{
+    Code, Status,
+    metadata::{MetadataMap, MetadataValue},
+};
+
+use crate::fixtures::{sample_error, sample_request};
+use crate::generated::{OwnedSampleError, hello_sample_error, hello_sample_mock::MockHelloSample};
+
+#[tokio::test]
+async fn test_status_details() {
+    let mock = MockHelloSample::new();
+    mock.say_hello2
+        .respond_err(hello_sample_error::say_hello2(
+            Code::Unavailable,
+            "try later",
+            sample_error("overloaded", 250),
+        ))
+        .respond_err(Status::unavailable("no details"));
+    let mut client = mock.into_client();
+
+    let Err(status) = client.say_hello2(sample_request("a")).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(status.message(), "try later");
+    // `grpc-status-details-bin` is left to `google.rpc.Status`.
+    assert!(status.details().is_empty());
+    let details = hello_sample_error::say_hello2_details(&status)
+        .expect("details are set")
+        .unwrap();
+    assert_eq!(details.get_ref().reason(), Some("overloaded"));
+    assert_eq!(details.get_ref().retry_after_ms(), 250);
+
+    let Err(status) = client.say_hello2(sample_request("b")).await else {
+        panic!("expected an error");
+    };
+    assert!(hello_sample_error::say_hello2_details(&status).is_none());
+}
+
+#[test]
+fn test_malformed_status_details() {
+    let mut metadata = MetadataMap::new();
+    metadata.insert_bin(DETAILS_KEY, MetadataValue::from_bytes(b"xx"));
+    let status = Status::with_metadata(Code::Internal, "bad", metadata);
+    assert!(status.fb_details::<OwnedSampleError>().unwrap().is_err());
+
+    // Protobuf details are not read as a flatbuffer.
+    let status = Status::with_details(Code::Internal, "bad", bytes::Bytes::from_static(b"xx"));
+    assert!(status.fb_details::<OwnedSampleError>().is_none());
+}
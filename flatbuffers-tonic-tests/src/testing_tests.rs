@@ -0,0 +1,80 @@
+use flatbuffers_tonic::testing::{TestServer, connect};
+use tokio_stream::StreamExt;
+use tonic::{Code, service::Routes};
+
+use crate::fixtures::{
+    client_stream_request, hello_request, hello_sample_mock, sample_mock, sample_request,
+    server_stream_request,
+};
+use crate::generated::{
+    greeter_client::GreeterClient, greeter_server::GreeterServer,
+    hello_sample_client::HelloSampleClient, hello_sample_server::HelloSampleServer,
+    sample_client::SampleClient, sample_server::SampleServer,
+};
+use crate::tonic_tests::Greeter;
+
+#[tokio::test]
+async fn test_connect_unary() {
+    let (server, mut client) = connect(GreeterServer::new(Greeter {}), GreeterClient::new);
+    let reply = client
+        .say_hello(hello_request("duplex"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello duplex"));
+
+    server.shutdown().await.unwrap();
+    let Err(status) = client.say_hello(hello_request("closed")).await else {
+        panic!("expected the call to fail after shutdown");
+    };
+    assert_eq!(status.code(), Code::Unavailable);
+}
+
+#[tokio::test]
+async fn test_test_server_streaming() {
+    let server = TestServer::new(
+        Routes::new(SampleServer::new(sample_mock()))
+            .add_service(HelloSampleServer::new(hello_sample_mock())),
+    );
+    let mut hello_client = HelloSampleClient::new(server.channel());
+    let mut client = SampleClient::new(server.channel());
+
+    let reply = hello_client
+        .say_hello(sample_request("routes"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reply.get_ref().message(), Some("hello routes"));
+
+    let requests = tokio_stream::iter((0..10).map(client_stream_request));
+    let reply = client.client_stream(requests).await.unwrap().into_inner();
+    assert_eq!(reply.get_ref().count(), 10);
+
+    let mut stream = client
+        .server_stream(server_stream_request(3))
+        .await
+        .unwrap()
+        .into_inner();
+    let mut messages = Vec::new();
+    while let Some(msg) = stream.next().await {
+        messages.push(msg.unwrap().get_ref().message().unwrap().to_string());
+    }
+    assert_eq!(
+        messages,
+        [
+            "server response 0",
+            "server response 1",
+            "server response 2"
+        ]
+    );
+
+    let requests = tokio_stream::iter(["a", "b", "c"].map(sample_request));
+    let mut stream = client.bidi_stream(requests).await.unwrap().into_inner();
+    let mut messages = Vec::new();
+    while let Some(msg) = stream.next().await {
+        messages.push(msg.unwrap().get_ref().message().unwrap().to_string());
+    }
+    assert_eq!(messages, ["hello a", "hello b", "hello c"]);
+
+    server.shutdown().await.unwrap();
+}
@@ -94,8 +94,8 @@ mod sample_test {
     impl crate::generated::hello_sample_server::HelloSample for HelloSampleSvc {
         async fn say_hello(
             &self,
-            request: tonic::Request<crate::generated::Ownedsample_request>,
-        ) -> Result<tonic::Response<crate::generated::Ownedsample_reply>, tonic::Status> {
+            request: tonic::Request<crate::generated::OwnedSampleRequest>,
+        ) -> Result<tonic::Response<crate::generated::OwnedSampleReply>, tonic::Status> {
             let request = request.into_inner();
             let name = request.get_ref().name();
             println!("Got a name: {name:?}");
@@ -115,8 +115,8 @@ mod sample_test {
 
         async fn say_hello2(
             &self,
-            request: tonic::Request<crate::generated::Ownedsample_request>,
-        ) -> Result<tonic::Response<crate::generated::Ownedsample_reply>, tonic::Status> {
+            request: tonic::Request<crate::generated::OwnedSampleRequest>,
+        ) -> Result<tonic::Response<crate::generated::OwnedSampleReply>, tonic::Status> {
             let request = request.into_inner();
             let name = request.get_ref().name();
             println!("(say_hello2) Got a name: {name:?}");
@@ -141,8 +141,8 @@ mod sample_test {
     impl crate::generated::sample_server::Sample for SampleSvc {
         async fn client_stream(
             &self,
-            request: tonic::Request<tonic::Streaming<crate::generated::Ownedclient_stream_request>>,
-        ) -> Result<tonic::Response<crate::generated::Ownedclient_stream_response>, tonic::Status>
+            request: tonic::Request<tonic::Streaming<crate::generated::OwnedClientStreamRequest>>,
+        ) -> Result<tonic::Response<crate::generated::OwnedClientStreamResponse>, tonic::Status>
         {
             // read all the stream chunks and count
             let mut stream = request.into_inner();
@@ -165,7 +165,7 @@ mod sample_test {
         type server_streamStream = std::pin::Pin<
             Box<
                 dyn tokio_stream::Stream<
-                        Item = Result<crate::generated::Ownedserver_stream_response, tonic::Status>,
+                        Item = Result<crate::generated::OwnedServerStreamResponse, tonic::Status>,
                     > + Send
                     + 'static,
             >,
@@ -173,7 +173,7 @@ mod sample_test {
 
         async fn server_stream(
             &self,
-            request: tonic::Request<crate::generated::Ownedserver_stream_request>,
+            request: tonic::Request<crate::generated::OwnedServerStreamRequest>,
         ) -> Result<tonic::Response<Self::server_streamStream>, tonic::Status> {
             let request = request.into_inner();
             let count = request.get_ref().count();
@@ -198,7 +198,7 @@ mod sample_test {
         type bidi_streamStream = std::pin::Pin<
             Box<
                 dyn tokio_stream::Stream<
-                        Item = Result<crate::generated::Ownedsample_reply, tonic::Status>,
+                        Item = Result<crate::generated::OwnedSampleReply, tonic::Status>,
                     > + Send
                     + 'static,
             >,
@@ -206,7 +206,7 @@ mod sample_test {
 
         async fn bidi_stream(
             &self,
-            request: tonic::Request<tonic::Streaming<crate::generated::Ownedsample_request>>,
+            request: tonic::Request<tonic::Streaming<crate::generated::OwnedSampleRequest>>,
         ) -> Result<tonic::Response<Self::bidi_streamStream>, tonic::Status> {
             let mut in_stream = request.into_inner();
             let (tx, rx) = mpsc::channel(128);
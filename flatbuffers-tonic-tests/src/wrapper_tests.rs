@@ -0,0 +1,56 @@
+//! Why rpc messages need `owned_wrapper!` rather than aliases of `OwnedFB`.
+//!
+//! The generated servers box the future of each call, and rustc fails to prove it
+//! `Send` once the message type is `OwnedFB<Table<'static>>`: the `'static` of the
+//! table is treated as any lifetime, for which `Verifiable` and the service are not
+//! implemented. Both tests below do what a generated unary server does, only the
+//! message types differ.
+//!
+//! With wrappers it compiles:
+//! ```
+//! use flatbuffers_tonic_tests::generated::{OwnedHelloReply, OwnedHelloRequest};
+//! # use flatbuffers_tonic::FlatBuffersServerCodec;
+//! # use tonic::codegen::{BoxFuture, http};
+//! # struct SayHello;
+//! # impl tonic::server::UnaryService<OwnedHelloRequest> for SayHello {
+//! #     type Response = OwnedHelloReply;
+//! #     type Future = BoxFuture<tonic::Response<OwnedHelloReply>, tonic::Status>;
+//! #     fn call(&mut self, _: tonic::Request<OwnedHelloRequest>) -> Self::Future {
+//! #         Box::pin(async { Err(tonic::Status::unimplemented("")) })
+//! #     }
+//! # }
+//! fn serve(
+//!     req: http::Request<tonic::body::Body>,
+//! ) -> BoxFuture<http::Response<tonic::body::Body>, std::convert::Infallible> {
+//!     Box::pin(async move {
+//!         let codec = FlatBuffersServerCodec::<OwnedHelloReply, OwnedHelloRequest>::default();
+//!         Ok(tonic::server::Grpc::new(codec).unary(SayHello, req).await)
+//!     })
+//! }
+//! ```
+//!
+//! With aliases the future is not `Send`:
+//! ```compile_fail
+//! use flatbuffers_tonic::OwnedFB;
+//! use flatbuffers_tonic_tests::generated::fbs::helloworld::{HelloReply, HelloRequest};
+//! type OwnedHelloRequest = OwnedFB<HelloRequest<'static>>;
+//! type OwnedHelloReply = OwnedFB<HelloReply<'static>>;
+//! # use flatbuffers_tonic::FlatBuffersServerCodec;
+//! # use tonic::codegen::{BoxFuture, http};
+//! # struct SayHello;
+//! # impl tonic::server::UnaryService<OwnedHelloRequest> for SayHello {
+//! #     type Response = OwnedHelloReply;
+//! #     type Future = BoxFuture<tonic::Response<OwnedHelloReply>, tonic::Status>;
+//! #     fn call(&mut self, _: tonic::Request<OwnedHelloRequest>) -> Self::Future {
+//! #         Box::pin(async { Err(tonic::Status::unimplemented("")) })
+//! #     }
+//! # }
+//! fn serve(
+//!     req: http::Request<tonic::body::Body>,
+//! ) -> BoxFuture<http::Response<tonic::body::Body>, std::convert::Infallible> {
+//!     Box::pin(async move {
+//!         let codec = FlatBuffersServerCodec::<OwnedHelloReply, OwnedHelloRequest>::default();
+//!         Ok(tonic::server::Grpc::new(codec).unary(SayHello, req).await)
+//!     })
+//! }
+//! ```
@@ -6,19 +6,37 @@ use tonic::{
     codec::{BufferSettings, Codec, Decoder, EncodeBuf, Encoder},
 };
 
-use crate::OwnedFBCodecable;
+use crate::{
+    OwnedFBCodecable, Side,
+    error::{DecodeError, report_decode_error},
+};
 
 /// TODO: codec still has copy step due to tonic DecodeBuf and EncodeBuf implementation.
+///
+/// Compression is applied by tonic to every message once `send_compressed` is set on the
+/// generated stubs. To skip compression for small messages use `CompressionLayer` instead.
+///
+/// This is the client side codec, malformed messages fail with `Internal`.
+/// Generated servers use [`FlatBuffersServerCodec`].
 #[derive(Debug, Clone)]
 pub struct FlatBuffersCodec<T, U> {
     _pd: PhantomData<(T, U)>,
+    side: Side,
 }
 
 impl<T, U> FlatBuffersCodec<T, U> {
     /// Configure a FlatBuffersCodec with encoder/decoder buffer settings. This is used to control
     /// how memory is allocated and grows per RPC.
     pub fn new() -> Self {
-        Self { _pd: PhantomData }
+        Self::with_side(Side::Client)
+    }
+
+    /// Codec for the given side, which decides the status of malformed messages.
+    pub fn with_side(side: Side) -> Self {
+        Self {
+            _pd: PhantomData,
+            side,
+        }
     }
 }
 
@@ -50,10 +68,41 @@ where
         FlatBuffersDecoder {
             _pd: PhantomData,
             buffer_settings: BufferSettings::default(),
+            side: self.side,
         }
     }
 }
 
+/// Codec of the generated servers, malformed requests fail with `InvalidArgument`.
+#[derive(Debug, Clone)]
+pub struct FlatBuffersServerCodec<T, U>(FlatBuffersCodec<T, U>);
+
+impl<T, U> Default for FlatBuffersServerCodec<T, U> {
+    fn default() -> Self {
+        Self(FlatBuffersCodec::with_side(Side::Server))
+    }
+}
+
+impl<T, U> Codec for FlatBuffersServerCodec<T, U>
+where
+    T: OwnedFBCodecable + Send + 'static,
+    U: OwnedFBCodecable + Send + 'static,
+{
+    type Encode = T;
+    type Decode = U;
+
+    type Encoder = FlatBuffersEncoder<T>;
+    type Decoder = FlatBuffersDecoder<U>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.0.encoder()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.0.decoder()
+    }
+}
+
 /// A [`Encoder`] that knows how to encode `T`.
 #[derive(Debug, Clone, Default)]
 pub struct FlatBuffersEncoder<T> {
@@ -85,6 +134,7 @@ where
 pub struct FlatBuffersDecoder<U> {
     _pd: PhantomData<U>,
     buffer_settings: BufferSettings,
+    side: Side,
 }
 
 impl<U: OwnedFBCodecable + Send + 'static> Decoder for FlatBuffersDecoder<U> {
@@ -98,11 +148,12 @@ impl<U: OwnedFBCodecable + Send + 'static> Decoder for FlatBuffersDecoder<U> {
     ) -> Result<Option<Self::Item>, Self::Error> {
         // First should be zero copy due to BytesMut impl.
         let buf = src.copy_to_bytes(src.remaining());
-        // This is not zero copy because DecodeBuf is a shared BytesMut.
-        // Even if it is not shared, BytesMut may have multiple chunks.
-        // Flatbuffer need contiguous memory, so this will make a copy in most cases.
-        let owned_fb = U::new_from_bytes(buf)
-            .map_err(|e| Status::internal(format!("Failed to decode FlatBuffer: {}", e)))?;
+        // Verified in place, the message keeps the bytes without copying them.
+        let owned_fb = U::new_from_bytes(buf).map_err(|e| {
+            let error = DecodeError::new(self.side, std::any::type_name::<U>(), e);
+            report_decode_error(&error);
+            Status::from(error)
+        })?;
         Ok(Some(owned_fb))
     }
 
@@ -0,0 +1,189 @@
+use std::{
+    io,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http::HeaderValue;
+use tonic::{
+    Status, body::Body, codec::CompressionEncoding, codegen::BoxFuture, server::NamedService,
+};
+
+use crate::{
+    Direction, InspectContext, Side,
+    encoding::{ENCODING_HEADER, compress},
+    frame::{FrameBody, FrameTransform},
+};
+const ACCEPT_ENCODING_HEADER: &str = "grpc-accept-encoding";
+
+#[derive(Debug, Clone, Copy)]
+struct Compressor {
+    encoding: CompressionEncoding,
+    name: &'static str,
+    threshold: usize,
+}
+
+impl FrameTransform for Compressor {
+    fn transform(&self, ctx: &InspectContext, message: Bytes) -> Result<(bool, Bytes), Status> {
+        if ctx.compressed() || message.len() < self.threshold {
+            return Ok((ctx.compressed(), message));
+        }
+        let message = compress(self.encoding, &message)
+            .map_err(|e| Status::internal(format!("Failed to compress message: {e}")))?;
+        Ok((true, message))
+    }
+}
+
+fn encoding_name(encoding: CompressionEncoding) -> Option<&'static str> {
+    match encoding {
+        #[cfg(feature = "gzip")]
+        CompressionEncoding::Gzip => Some("gzip"),
+        #[cfg(feature = "zstd")]
+        CompressionEncoding::Zstd => Some("zstd"),
+        _ => None,
+    }
+}
+
+/// Tower layer that compresses only the messages at or above a size threshold.
+///
+/// `send_compressed` on the generated stubs makes tonic compress every message, which
+/// costs more than it saves for small flatbuffers. With this layer tonic sends messages
+/// uncompressed and the layer compresses the large ones, setting the per message
+/// compressed flag. The receiving side only needs `accept_compressed` as usual.
+///
+/// Use [`CompressionLayer::client`] on a `Channel` and [`CompressionLayer::server`]
+/// with `Server::builder().layer(..)`, or on a single generated `*Server`.
+#[derive(Debug, Clone)]
+pub struct CompressionLayer {
+    side: Side,
+    compressor: Compressor,
+}
+
+impl CompressionLayer {
+    /// Messages smaller than this are sent uncompressed by default.
+    pub const DEFAULT_THRESHOLD: usize = 1024;
+
+    /// Compress requests sent by a client. The server must accept the encoding.
+    ///
+    /// Fails with `Unsupported` unless the `gzip` or `zstd` feature of the encoding is enabled.
+    pub fn client(encoding: CompressionEncoding) -> io::Result<Self> {
+        Self::new(Side::Client, encoding)
+    }
+
+    /// Compress responses for clients that accept the encoding.
+    ///
+    /// Fails with `Unsupported` unless the `gzip` or `zstd` feature of the encoding is enabled.
+    pub fn server(encoding: CompressionEncoding) -> io::Result<Self> {
+        Self::new(Side::Server, encoding)
+    }
+
+    fn new(side: Side, encoding: CompressionEncoding) -> io::Result<Self> {
+        let name = encoding_name(encoding).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{encoding} compression is not supported"),
+            )
+        })?;
+        Ok(Self {
+            side,
+            compressor: Compressor {
+                encoding,
+                name,
+                threshold: Self::DEFAULT_THRESHOLD,
+            },
+        })
+    }
+
+    /// Set the size in bytes from which messages are compressed.
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.compressor.threshold = threshold;
+        self
+    }
+}
+
+impl<S> tower_layer::Layer<S> for CompressionLayer {
+    type Service = CompressionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CompressionService {
+            inner,
+            side: self.side,
+            compressor: Arc::new(self.compressor),
+        }
+    }
+}
+
+/// Service created by [`CompressionLayer`].
+#[derive(Debug, Clone)]
+pub struct CompressionService<S> {
+    inner: S,
+    side: Side,
+    compressor: Arc<Compressor>,
+}
+
+impl<S: NamedService> NamedService for CompressionService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S> tower_service::Service<http::Request<Body>> for CompressionService<S>
+where
+    S: tower_service::Service<http::Request<Body>, Response = http::Response<Body>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+        let path: Arc<str> = Arc::from(req.uri().path());
+        let compressor = self.compressor.clone();
+        let name = compressor.name;
+        match self.side {
+            Side::Client => {
+                // Leave requests alone that tonic already compresses.
+                if req.headers().contains_key(ENCODING_HEADER) {
+                    return Box::pin(self.inner.call(req));
+                }
+                let (mut parts, body) = req.into_parts();
+                parts
+                    .headers
+                    .insert(ENCODING_HEADER, HeaderValue::from_static(name));
+                // The messages are our own, so they are not limited in size.
+                let ctx = InspectContext::new(path, Direction::Request, None);
+                let body = FrameBody::new(body, compressor, ctx, usize::MAX);
+                Box::pin(
+                    self.inner
+                        .call(http::Request::from_parts(parts, Body::new(body))),
+                )
+            }
+            Side::Server => {
+                let accepted = req
+                    .headers()
+                    .get_all(ACCEPT_ENCODING_HEADER)
+                    .iter()
+                    .filter_map(|v| v.to_str().ok())
+                    .flat_map(|v| v.split(','))
+                    .any(|e| e.trim() == name);
+                let fut = self.inner.call(req);
+                Box::pin(async move {
+                    let resp = fut.await?;
+                    if !accepted || resp.headers().contains_key(ENCODING_HEADER) {
+                        return Ok(resp);
+                    }
+                    let (mut parts, body) = resp.into_parts();
+                    parts
+                        .headers
+                        .insert(ENCODING_HEADER, HeaderValue::from_static(name));
+                    let ctx = InspectContext::new(path, Direction::Response, None);
+                    let body = FrameBody::new(body, compressor, ctx, usize::MAX);
+                    Ok(http::Response::from_parts(parts, Body::new(body)))
+                })
+            }
+        }
+    }
+}
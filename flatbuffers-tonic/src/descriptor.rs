@@ -0,0 +1,31 @@
+/// Description of a generated service, the `DESCRIPTOR` constant of its
+/// `<service>_attributes` module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceDescriptor {
+    /// Fully qualified name, i.e. `fbs.helloworld.Greeter`.
+    pub name: &'static str,
+    /// Methods in schema order.
+    pub methods: &'static [MethodDescriptor],
+}
+
+impl ServiceDescriptor {
+    /// The method with the gRPC `path`, i.e. `/fbs.helloworld.Greeter/SayHello`.
+    pub fn method(&self, path: &str) -> Option<&'static MethodDescriptor> {
+        self.methods.iter().find(|method| method.path == path)
+    }
+}
+
+/// Description of a method of a generated service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodDescriptor {
+    /// Name in the schema, i.e. `SayHello`.
+    pub name: &'static str,
+    /// gRPC path, i.e. `/fbs.helloworld.Greeter/SayHello`.
+    pub path: &'static str,
+    /// Fully qualified request table, i.e. `fbs.helloworld.HelloRequest`.
+    pub request: &'static str,
+    /// Fully qualified response table.
+    pub response: &'static str,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
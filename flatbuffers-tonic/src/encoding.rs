@@ -0,0 +1,94 @@
+use std::io;
+
+use bytes::Bytes;
+use tonic::{Status, codec::CompressionEncoding};
+
+pub(crate) const ENCODING_HEADER: &str = "grpc-encoding";
+
+/// Largest message the layers buffer by default, the same as tonic's decoding limit.
+pub(crate) const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Encoding of the compressed messages of a stream, `None` if it is unset or not enabled.
+pub(crate) fn stream_encoding(headers: &http::HeaderMap) -> Option<CompressionEncoding> {
+    match headers.get(ENCODING_HEADER)?.to_str().ok()? {
+        #[cfg(feature = "gzip")]
+        "gzip" => Some(CompressionEncoding::Gzip),
+        #[cfg(feature = "zstd")]
+        "zstd" => Some(CompressionEncoding::Zstd),
+        _ => None,
+    }
+}
+
+#[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+pub(crate) fn compress(encoding: CompressionEncoding, message: &[u8]) -> io::Result<Bytes> {
+    match encoding {
+        #[cfg(feature = "gzip")]
+        CompressionEncoding::Gzip => {
+            // Same level as tonic.
+            let mut encoder = flate2::read::GzEncoder::new(message, flate2::Compression::new(6));
+            let mut out = Vec::new();
+            io::copy(&mut encoder, &mut out)?;
+            Ok(out.into())
+        }
+        #[cfg(feature = "zstd")]
+        CompressionEncoding::Zstd => {
+            Ok(zstd::stream::encode_all(message, zstd::DEFAULT_COMPRESSION_LEVEL)?.into())
+        }
+        other => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{other} is not supported"),
+        )),
+    }
+}
+
+/// Decompress a message, failing with `ResourceExhausted` once it grows past `limit` bytes.
+pub(crate) fn decompress(
+    encoding: CompressionEncoding,
+    message: &[u8],
+    limit: usize,
+) -> Result<Bytes, Status> {
+    let message = decompress_limited(encoding, message, limit).map_err(|e| match e.kind() {
+        io::ErrorKind::Unsupported => Status::unimplemented(e.to_string()),
+        _ => Status::internal(format!("Failed to decompress message: {e}")),
+    })?;
+    if message.len() > limit {
+        return Err(too_large(limit));
+    }
+    Ok(message)
+}
+
+#[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+fn decompress_limited(
+    encoding: CompressionEncoding,
+    message: &[u8],
+    limit: usize,
+) -> io::Result<Bytes> {
+    match encoding {
+        #[cfg(feature = "gzip")]
+        CompressionEncoding::Gzip => read_limited(flate2::read::GzDecoder::new(message), limit),
+        #[cfg(feature = "zstd")]
+        CompressionEncoding::Zstd => {
+            read_limited(zstd::stream::read::Decoder::new(message)?, limit)
+        }
+        other => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{other} compression is not supported"),
+        )),
+    }
+}
+
+/// Reads at most one byte past the limit, for [`decompress`] to tell it was exceeded.
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+fn read_limited(reader: impl io::Read, limit: usize) -> io::Result<Bytes> {
+    use io::Read;
+
+    let mut out = Vec::new();
+    reader
+        .take((limit as u64).saturating_add(1))
+        .read_to_end(&mut out)?;
+    Ok(out.into())
+}
+
+pub(crate) fn too_large(limit: usize) -> Status {
+    Status::resource_exhausted(format!("message is larger than the limit of {limit} bytes"))
+}
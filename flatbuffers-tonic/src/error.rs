@@ -0,0 +1,149 @@
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use flatbuffers::{ErrorTraceDetail, InvalidFlatbuffer};
+use tonic::{Code, Status};
+
+/// Which end of the RPC a codec or layer runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Client,
+    Server,
+}
+
+/// A received message that failed FlatBuffers verification.
+///
+/// The codec fails the RPC with a status carrying this error as its source:
+/// `InvalidArgument` for requests received by a server, since the peer sent garbage,
+/// and `Internal` for responses received by a client.
+#[derive(Debug, Clone)]
+pub struct DecodeError {
+    side: Side,
+    message_type: &'static str,
+    error: InvalidFlatbuffer,
+}
+
+impl DecodeError {
+    pub(crate) fn new(side: Side, message_type: &'static str, error: InvalidFlatbuffer) -> Self {
+        Self {
+            side,
+            message_type,
+            error,
+        }
+    }
+
+    /// The side that received the malformed message.
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    /// Rust type name of the message that was decoded.
+    pub fn message_type(&self) -> &'static str {
+        self.message_type
+    }
+
+    /// The verifier error, telling what kind of corruption was found.
+    pub fn error(&self) -> &InvalidFlatbuffer {
+        &self.error
+    }
+
+    /// Path of the offending field from the root table, i.e. `items[2].name`.
+    /// Empty if the error is not tied to a field, like a too large buffer.
+    pub fn path(&self) -> String {
+        let Some(trace) = error_trace(&self.error) else {
+            return String::new();
+        };
+        // The trace starts at the innermost field.
+        let mut path = String::new();
+        for detail in trace.iter().rev() {
+            match detail {
+                ErrorTraceDetail::TableField { field_name, .. } => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(field_name);
+                }
+                ErrorTraceDetail::VectorElement { index, .. } => {
+                    path.push_str(&format!("[{index}]"));
+                }
+                ErrorTraceDetail::UnionVariant { variant, .. } => {
+                    path.push_str(&format!("<{variant}>"));
+                }
+            }
+        }
+        path
+    }
+
+    /// Status code reported for the error.
+    pub fn code(&self) -> Code {
+        match self.side {
+            Side::Server => Code::InvalidArgument,
+            Side::Client => Code::Internal,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to decode FlatBuffer {}", self.message_type)?;
+        let path = self.path();
+        if !path.is_empty() {
+            write!(f, " at `{path}`")?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<DecodeError> for Status {
+    fn from(error: DecodeError) -> Self {
+        let mut status = Status::new(error.code(), error.to_string());
+        status.set_source(Arc::new(error));
+        status
+    }
+}
+
+fn error_trace(error: &InvalidFlatbuffer) -> Option<&[ErrorTraceDetail]> {
+    match error {
+        InvalidFlatbuffer::MissingRequiredField { error_trace, .. }
+        | InvalidFlatbuffer::InconsistentUnion { error_trace, .. }
+        | InvalidFlatbuffer::Utf8Error { error_trace, .. }
+        | InvalidFlatbuffer::MissingNullTerminator { error_trace, .. }
+        | InvalidFlatbuffer::Unaligned { error_trace, .. }
+        | InvalidFlatbuffer::RangeOutOfBounds { error_trace, .. }
+        | InvalidFlatbuffer::SignedOffsetOutOfBounds { error_trace, .. } => {
+            Some(error_trace.as_ref())
+        }
+        _ => None,
+    }
+}
+
+type DecodeErrorHook = Arc<dyn Fn(&DecodeError) + Send + Sync>;
+
+static DECODE_ERROR_HOOK: RwLock<Option<DecodeErrorHook>> = RwLock::new(None);
+
+/// Install a process wide callback run for every message the codec fails to verify,
+/// i.e. to count malformed traffic. Replaces the previous hook.
+pub fn set_decode_error_hook<F>(hook: F)
+where
+    F: Fn(&DecodeError) + Send + Sync + 'static,
+{
+    *DECODE_ERROR_HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(hook));
+}
+
+pub(crate) fn report_decode_error(error: &DecodeError) {
+    let hook = DECODE_ERROR_HOOK
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(hook) = hook {
+        hook(error);
+    }
+}
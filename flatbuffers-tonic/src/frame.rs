@@ -0,0 +1,165 @@
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, ready},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http_body::Frame;
+use tonic::{Status, body::Body};
+
+use crate::{
+    encoding::too_large,
+    inspect::{Direction, InspectContext},
+};
+
+/// gRPC message frame header: 1 byte compressed flag and 4 bytes big endian length.
+const HEADER_SIZE: usize = 5;
+
+/// Rewrites a single gRPC message, returning the compressed flag with the payload.
+/// `ctx.compressed()` is the flag of the given message.
+pub(crate) trait FrameTransform: Send + Sync + 'static {
+    fn transform(&self, ctx: &InspectContext, message: Bytes) -> Result<(bool, Bytes), Status>;
+}
+
+/// Body that splits the gRPC stream into messages and runs the [`FrameTransform`] on each.
+pub(crate) struct FrameBody<T> {
+    inner: Body,
+    transform: Arc<T>,
+    ctx: InspectContext,
+    max_message_size: usize,
+    buf: BytesMut,
+    pending: Option<Frame<Bytes>>,
+    rejected: Option<Status>,
+    done: bool,
+}
+
+impl<T: FrameTransform> FrameBody<T> {
+    /// Messages declared larger than `max_message_size` fail with `ResourceExhausted`
+    /// before they are buffered.
+    pub(crate) fn new(
+        inner: Body,
+        transform: Arc<T>,
+        ctx: InspectContext,
+        max_message_size: usize,
+    ) -> Self {
+        Self {
+            inner,
+            transform,
+            ctx,
+            max_message_size,
+            buf: BytesMut::new(),
+            pending: None,
+            rejected: None,
+            done: false,
+        }
+    }
+
+    /// Transform all complete messages in the buffer and return them re-framed.
+    /// Stops at the first rejected message.
+    fn drain(&mut self) -> Bytes {
+        let mut out = BytesMut::new();
+        while self.buf.len() >= HEADER_SIZE {
+            let len = u32::from_be_bytes([self.buf[1], self.buf[2], self.buf[3], self.buf[4]]);
+            if len as usize > self.max_message_size {
+                self.buf.clear();
+                self.rejected = Some(too_large(self.max_message_size));
+                break;
+            }
+            let frame_len = HEADER_SIZE + len as usize;
+            if self.buf.len() < frame_len {
+                break;
+            }
+            let frame = self.buf.split_to(frame_len).freeze();
+            self.ctx.compressed = frame[0] != 0;
+            match self
+                .transform
+                .transform(&self.ctx, frame.slice(HEADER_SIZE..))
+            {
+                Ok((compressed, message)) => {
+                    out.reserve(HEADER_SIZE + message.len());
+                    out.put_u8(compressed as u8);
+                    out.put_u32(message.len() as u32);
+                    out.put(message);
+                }
+                Err(status) => {
+                    self.buf.clear();
+                    self.rejected = Some(status);
+                    break;
+                }
+            }
+            self.ctx.index += 1;
+        }
+        out.freeze()
+    }
+
+    /// Requests fail with the status as the body error, which tonic hands to the
+    /// handler (server) or reports for the call (client). Responses end with the
+    /// status in the trailers so the peer sees a regular gRPC error.
+    fn reject(&mut self, status: Status) -> Option<Result<Frame<Bytes>, Status>> {
+        self.done = true;
+        if self.ctx.direction == Direction::Response {
+            let mut trailers = http::HeaderMap::new();
+            if let Err(status) = status.add_header(&mut trailers) {
+                return Some(Err(status));
+            }
+            return Some(Ok(Frame::trailers(trailers)));
+        }
+        Some(Err(status))
+    }
+}
+
+impl<T: FrameTransform> http_body::Body for FrameBody<T> {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        loop {
+            if let Some(frame) = this.pending.take() {
+                return Poll::Ready(Some(Ok(frame)));
+            }
+            if let Some(status) = this.rejected.take() {
+                return Poll::Ready(this.reject(status));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            match ready!(Pin::new(&mut this.inner).poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => {
+                        this.buf.extend_from_slice(&data);
+                        let out = this.drain();
+                        if !out.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::data(out))));
+                        }
+                    }
+                    // Trailers. A partial message left in the buffer is passed on for
+                    // the decoder to report.
+                    Err(frame) if !this.buf.is_empty() => {
+                        this.pending = Some(frame);
+                        return Poll::Ready(Some(Ok(Frame::data(this.buf.split().freeze()))));
+                    }
+                    Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                },
+                Some(Err(status)) => return Poll::Ready(Some(Err(status))),
+                None => {
+                    this.done = true;
+                    if !this.buf.is_empty() {
+                        return Poll::Ready(Some(Ok(Frame::data(this.buf.split().freeze()))));
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.pending.is_none()
+            && self.rejected.is_none()
+            && self.buf.is_empty()
+            && (self.done || self.inner.is_end_stream())
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use tonic::{
+    Status, body::Body, codec::CompressionEncoding, codegen::BoxFuture, server::NamedService,
+};
+
+use crate::{
+    OwnedFBCodecable,
+    encoding::{DEFAULT_MAX_MESSAGE_SIZE, compress, decompress, stream_encoding},
+    frame::{FrameBody, FrameTransform},
+};
+
+/// Which way a message travels in the RPC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Request,
+    Response,
+}
+
+/// Describes the message being inspected.
+#[derive(Debug, Clone)]
+pub struct InspectContext {
+    pub(crate) path: Arc<str>,
+    pub(crate) direction: Direction,
+    pub(crate) index: usize,
+    pub(crate) compressed: bool,
+    pub(crate) encoding: Option<CompressionEncoding>,
+}
+
+impl InspectContext {
+    /// Context of the first message of a stream, compressed with `encoding` if at all.
+    pub(crate) fn new(
+        path: Arc<str>,
+        direction: Direction,
+        encoding: Option<CompressionEncoding>,
+    ) -> Self {
+        Self {
+            path,
+            direction,
+            index: 0,
+            compressed: false,
+            encoding,
+        }
+    }
+
+    /// The gRPC path of the RPC, i.e. `/fbs.helloworld.Greeter/SayHello`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The fully qualified service name, i.e. `fbs.helloworld.Greeter`.
+    pub fn service(&self) -> &str {
+        self.split_path().0
+    }
+
+    /// The method name, i.e. `SayHello`.
+    pub fn method(&self) -> &str {
+        self.split_path().1
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Position of the message in its stream, 0 for the first message.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The message was compressed on the wire. Inspectors get it decompressed,
+    /// and what they return is compressed again.
+    pub fn compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Verify and decode the message into its generated wrapper type.
+    /// Malformed requests map to `InvalidArgument`, malformed responses to `Internal`.
+    pub fn decode<T: OwnedFBCodecable>(&self, message: &Bytes) -> Result<T, Status> {
+        T::new_from_bytes(message.clone()).map_err(|e| {
+            let msg = format!("Failed to decode FlatBuffer for {}: {}", self.path, e);
+            match self.direction {
+                Direction::Request => Status::invalid_argument(msg),
+                Direction::Response => Status::internal(msg),
+            }
+        })
+    }
+
+    fn split_path(&self) -> (&str, &str) {
+        self.path
+            .trim_start_matches('/')
+            .split_once('/')
+            .unwrap_or((&self.path, ""))
+    }
+}
+
+/// Observes every message of the RPCs passing through [`InspectLayer`].
+///
+/// The inspector gets the raw flatbuffer bytes of each message and returns the
+/// bytes to forward, which may be the same buffer, a redacted copy, or an error
+/// status that fails the RPC. Compressed messages are decompressed first, and fail
+/// with `Unimplemented` if the encoding is not enabled with the `gzip` or `zstd` feature.
+pub trait Inspector: Send + Sync + 'static {
+    fn inspect(&self, ctx: &InspectContext, message: Bytes) -> Result<Bytes, Status>;
+}
+
+impl<F> Inspector for F
+where
+    F: Fn(&InspectContext, Bytes) -> Result<Bytes, Status> + Send + Sync + 'static,
+{
+    fn inspect(&self, ctx: &InspectContext, message: Bytes) -> Result<Bytes, Status> {
+        self(ctx, message)
+    }
+}
+
+/// An inspector with the limits of the layer.
+struct Inspect<I> {
+    inspector: Arc<I>,
+    max_message_size: usize,
+}
+
+impl<I: Inspector> FrameTransform for Inspect<I> {
+    fn transform(&self, ctx: &InspectContext, message: Bytes) -> Result<(bool, Bytes), Status> {
+        if !ctx.compressed {
+            return Ok((false, self.inspector.inspect(ctx, message)?));
+        }
+        // Forwarding compressed messages uninspected would let any peer skip the inspector.
+        let Some(encoding) = ctx.encoding else {
+            return Err(Status::unimplemented(
+                "compressed message with an encoding that cannot be inspected",
+            ));
+        };
+        let message = decompress(encoding, &message, self.max_message_size)?;
+        let message = self.inspector.inspect(ctx, message)?;
+        let message = compress(encoding, &message)
+            .map_err(|e| Status::internal(format!("Failed to compress message: {e}")))?;
+        Ok((true, message))
+    }
+}
+
+/// Tower layer that runs an [`Inspector`] on request and response messages.
+/// Works for all four RPC kinds, on the server via `Server::builder().layer(..)` or on a
+/// single generated `*Server`, and on the client by wrapping the `Channel`.
+///
+/// A rejected request fails the RPC with the inspector's status. A rejected
+/// response ends the response stream with the status in the trailers.
+/// Messages larger than the limit fail with `ResourceExhausted`.
+pub struct InspectLayer<I> {
+    inspector: Arc<I>,
+    max_message_size: usize,
+}
+
+impl<I: Inspector> InspectLayer<I> {
+    /// Largest message inspected by default, in bytes.
+    pub const DEFAULT_MAX_MESSAGE_SIZE: usize = DEFAULT_MAX_MESSAGE_SIZE;
+
+    pub fn new(inspector: I) -> Self {
+        Self {
+            inspector: Arc::new(inspector),
+            max_message_size: Self::DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Set the largest message in bytes, also after decompression.
+    pub fn max_message_size(mut self, limit: usize) -> Self {
+        self.max_message_size = limit;
+        self
+    }
+}
+
+impl<I> Clone for InspectLayer<I> {
+    fn clone(&self) -> Self {
+        Self {
+            inspector: self.inspector.clone(),
+            max_message_size: self.max_message_size,
+        }
+    }
+}
+
+impl<S, I> tower_layer::Layer<S> for InspectLayer<I> {
+    type Service = InspectService<S, I>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InspectService {
+            inner,
+            inspector: Arc::new(Inspect {
+                inspector: self.inspector.clone(),
+                max_message_size: self.max_message_size,
+            }),
+        }
+    }
+}
+
+/// Service created by [`InspectLayer`].
+pub struct InspectService<S, I> {
+    inner: S,
+    inspector: Arc<Inspect<I>>,
+}
+
+impl<S, I: Inspector> InspectService<S, I> {
+    /// Wrap `inner` with the default limits of [`InspectLayer`].
+    pub fn new(inner: S, inspector: I) -> Self {
+        tower_layer::Layer::layer(&InspectLayer::new(inspector), inner)
+    }
+}
+
+impl<S: Clone, I> Clone for InspectService<S, I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            inspector: self.inspector.clone(),
+        }
+    }
+}
+
+impl<S: NamedService, I> NamedService for InspectService<S, I> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, I> tower_service::Service<http::Request<Body>> for InspectService<S, I>
+where
+    S: tower_service::Service<http::Request<Body>, Response = http::Response<Body>>,
+    S::Future: Send + 'static,
+    I: Inspector,
+{
+    type Response = http::Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+        let path: Arc<str> = Arc::from(req.uri().path());
+        let inspector = self.inspector.clone();
+        let limit = inspector.max_message_size;
+        let ctx = InspectContext::new(
+            path.clone(),
+            Direction::Request,
+            stream_encoding(req.headers()),
+        );
+        let req = req.map(|body| Body::new(FrameBody::new(body, inspector.clone(), ctx, limit)));
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let resp = fut.await?;
+            let ctx =
+                InspectContext::new(path, Direction::Response, stream_encoding(resp.headers()));
+            Ok(resp.map(|body| Body::new(FrameBody::new(body, inspector, ctx, limit))))
+        })
+    }
+}
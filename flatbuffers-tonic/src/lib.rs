@@ -1,6 +1,47 @@
 mod codec;
-pub use codec::FlatBuffersCodec;
+pub use codec::{FlatBuffersCodec, FlatBuffersServerCodec};
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compression;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use compression::{CompressionLayer, CompressionService};
+pub use tonic::codec::CompressionEncoding;
+
+mod descriptor;
+pub use descriptor::{MethodDescriptor, ServiceDescriptor};
+
+mod encoding;
+
+mod error;
+pub use error::{DecodeError, Side, set_decode_error_hook};
+
+mod frame;
+
+mod inspect;
+pub use inspect::{Direction, InspectContext, InspectLayer, InspectService, Inspector};
+
+mod policy;
+pub use policy::CallPolicy;
+
+mod proxy;
+pub use proxy::GrpcProxy;
+
+mod raw;
+pub use raw::{RawFlatBuffers, RawFlatBuffersCodec};
+
+mod status;
+pub use status::{DETAILS_KEY, StatusExt, status_with_details};
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
 mod wrapper;
-pub use flatbuffers_util::{FBBuilder, OwnedFB};
+pub use flatbuffers_util::{FBBuilder, OwnedFB, OwnedFBMut, TableRef, impl_table_ref};
 pub use wrapper::OwnedFBCodecable;
+
+/// Paths used by the macros.
+#[doc(hidden)]
+pub mod __private {
+    pub use bytes::Bytes;
+    pub use flatbuffers::InvalidFlatbuffer;
+}
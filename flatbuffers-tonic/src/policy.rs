@@ -0,0 +1,137 @@
+use std::{future::Future, time::Duration};
+
+use tokio::time::Instant;
+use tonic::{Code, Request, Response, Status};
+
+/// Deadline and retry policy of an rpc method,
+/// generated from its `timeout`, `idempotent` and `retry` schema attributes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallPolicy {
+    /// Deadline of the call, retries included, unless the caller set one on the request.
+    pub timeout: Option<Duration>,
+    /// Times a call failing with `Unavailable` is retried, zero unless the method is idempotent.
+    pub retries: u32,
+}
+
+impl CallPolicy {
+    /// Retries of an `idempotent` method without a `retry` attribute.
+    pub const DEFAULT_RETRIES: u32 = 2;
+
+    /// Backoff before the first retry, doubled for each following retry.
+    const INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+    const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+    /// Set the policy timeout on the request, unless the caller already set a timeout.
+    pub fn apply<T>(&self, request: &mut Request<T>) {
+        match self.timeout {
+            Some(timeout) if !request.metadata().contains_key(TIMEOUT_HEADER) => {
+                request.set_timeout(timeout);
+            }
+            _ => {}
+        }
+    }
+
+    /// Run a call, failing with `DeadlineExceeded` when the response is not there by the
+    /// deadline, even if the server ignores `grpc-timeout`. The messages of a response
+    /// stream are only covered by the server.
+    pub async fn call<Req, Resp, F, Fut>(
+        &self,
+        mut request: Request<Req>,
+        call: F,
+    ) -> Result<Response<Resp>, Status>
+    where
+        F: FnOnce(Request<Req>) -> Fut,
+        Fut: Future<Output = Result<Response<Resp>, Status>>,
+    {
+        let deadline = self.deadline(&request);
+        self.apply(&mut request);
+        with_deadline(deadline, call(request)).await
+    }
+
+    /// Run a unary call, retrying it with backoff while it fails with `Unavailable`.
+    /// Each attempt sends a copy of the request made before the first one.
+    /// The deadline covers all attempts and the backoff between them.
+    pub async fn unary<Req, Resp, F, Fut>(
+        &self,
+        request: Request<Req>,
+        mut call: F,
+    ) -> Result<Response<Resp>, Status>
+    where
+        Req: Clone,
+        F: FnMut(Request<Req>) -> Fut,
+        Fut: Future<Output = Result<Response<Resp>, Status>>,
+    {
+        if self.retries == 0 {
+            return self.call(request, call).await;
+        }
+        let deadline = self.deadline(&request);
+        let (metadata, extensions, message) = request.into_parts();
+        let mut attempt = 0;
+        loop {
+            let mut request =
+                Request::from_parts(metadata.clone(), extensions.clone(), message.clone());
+            if let Some(deadline) = deadline {
+                // The server gets the time left for this attempt.
+                request.set_timeout(deadline.saturating_duration_since(Instant::now()));
+            }
+            match with_deadline(deadline, call(request)).await {
+                Err(status) if status.code() == Code::Unavailable && attempt < self.retries => {
+                    let backoff = self.backoff(attempt);
+                    if deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+                        return Err(deadline_exceeded());
+                    }
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// The timeout the caller set on the request, or else the policy timeout, from now.
+    fn deadline<T>(&self, request: &Request<T>) -> Option<Instant> {
+        request_timeout(request)
+            .or(self.timeout)
+            .map(|timeout| Instant::now() + timeout)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        Self::INITIAL_BACKOFF
+            .saturating_mul(1 << attempt.min(16))
+            .min(Self::MAX_BACKOFF)
+    }
+}
+
+const TIMEOUT_HEADER: &str = "grpc-timeout";
+
+/// The `grpc-timeout` of a request, i.e. `500m` or `7000000u`.
+fn request_timeout<T>(request: &Request<T>) -> Option<Duration> {
+    let value = request.metadata().get(TIMEOUT_HEADER)?.to_str().ok()?;
+    let (number, unit) = value.split_at(value.len().checked_sub(1)?);
+    let number: u64 = number.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(number.saturating_mul(60 * 60)),
+        "M" => Duration::from_secs(number.saturating_mul(60)),
+        "S" => Duration::from_secs(number),
+        "m" => Duration::from_millis(number),
+        "u" => Duration::from_micros(number),
+        "n" => Duration::from_nanos(number),
+        _ => return None,
+    })
+}
+
+async fn with_deadline<T>(
+    deadline: Option<Instant>,
+    call: impl Future<Output = Result<T, Status>>,
+) -> Result<T, Status> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, call)
+            .await
+            .unwrap_or_else(|_| Err(deadline_exceeded())),
+        None => call.await,
+    }
+}
+
+fn deadline_exceeded() -> Status {
+    Status::deadline_exceeded("deadline of the call policy exceeded")
+}
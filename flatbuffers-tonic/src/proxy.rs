@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    future::poll_fn,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use tonic::{
+    Status,
+    body::Body,
+    client::GrpcService,
+    codegen::{BoxFuture, StdError},
+    service::Routes,
+};
+
+use crate::ServiceDescriptor;
+
+/// Forwards RPCs to upstream services by gRPC path, streaming the HTTP/2 bodies
+/// through without verifying, decoding or decompressing the messages.
+///
+/// Routes are added per service with its generated `DESCRIPTOR`. Calls to other
+/// paths fail with `Unimplemented`. Request metadata, including the deadline and
+/// `grpc-encoding`, is forwarded, and so are the response metadata, messages and
+/// error status. Messages are not buffered, so no message size limit applies.
+/// A failed or cancelled request stream resets the upstream call rather than ending it.
+///
+/// ```ignore
+/// let proxy = GrpcProxy::new()
+///     .route(&greeter_attributes::DESCRIPTOR, greeter_channel)
+///     .route(&geometry_attributes::DESCRIPTOR, geometry_channel);
+/// Server::builder().add_routes(proxy.into_routes()).serve(addr).await?;
+/// ```
+pub struct GrpcProxy<T> {
+    routes: Arc<HashMap<&'static str, T>>,
+}
+
+impl<T> GrpcProxy<T> {
+    pub fn new() -> Self {
+        Self {
+            routes: Arc::new(HashMap::new()),
+        }
+    }
+
+    /// The gRPC paths served, in no particular order.
+    pub fn paths(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.routes.keys().copied()
+    }
+}
+
+impl<T> Default for GrpcProxy<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for GrpcProxy<T> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+impl<T> GrpcProxy<T>
+where
+    T: GrpcService<Body> + Clone + Send + Sync + 'static,
+    T::Error: Into<StdError>,
+    T::Future: Send,
+    T::ResponseBody: http_body::Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as http_body::Body>::Error: Into<StdError> + Send,
+{
+    /// Forward all methods of `service` to `upstream`, replacing earlier routes of them.
+    pub fn route(mut self, service: &ServiceDescriptor, upstream: T) -> Self {
+        let routes = Arc::make_mut(&mut self.routes);
+        for method in service.methods {
+            routes.insert(method.path, upstream.clone());
+        }
+        self
+    }
+
+    /// Routes with the proxy serving every path.
+    pub fn into_routes(self) -> Routes {
+        self.fallback_of(Routes::default())
+    }
+
+    /// Serve the paths that none of the services in `routes` serves with the proxy,
+    /// i.e. to implement some services locally and forward the others.
+    pub fn fallback_of(self, mut routes: Routes) -> Routes {
+        let router = std::mem::take(routes.axum_router_mut());
+        *routes.axum_router_mut() = router.fallback_service(self);
+        routes
+    }
+}
+
+impl<T, B> tower_service::Service<http::Request<B>> for GrpcProxy<T>
+where
+    T: GrpcService<Body> + Clone + Send + Sync + 'static,
+    T::Error: Into<StdError>,
+    T::Future: Send,
+    T::ResponseBody: http_body::Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as http_body::Body>::Error: Into<StdError> + Send,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<StdError>,
+{
+    type Response = http::Response<Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let upstream = self.routes.get(req.uri().path()).cloned();
+        Box::pin(async move {
+            let Some(upstream) = upstream else {
+                let status = Status::unimplemented(format!("no route for {}", req.uri().path()));
+                return Ok(status_response(status));
+            };
+            Ok(forward(upstream, req).await.unwrap_or_else(status_response))
+        })
+    }
+}
+
+/// Forwards one RPC to its upstream. Every kind of RPC is the same stream of
+/// messages on the wire, and the body errors of either side reach the other.
+async fn forward<T, B>(
+    mut upstream: T,
+    req: http::Request<B>,
+) -> Result<http::Response<Body>, Status>
+where
+    T: GrpcService<Body>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: http_body::Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as http_body::Body>::Error: Into<StdError> + Send,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<StdError>,
+{
+    poll_fn(|cx| upstream.poll_ready(cx))
+        .await
+        .map_err(|e| Status::unavailable(format!("upstream not ready: {}", e.into())))?;
+    let (mut parts, body) = req.into_parts();
+    // The upstream service sets its own origin.
+    parts.uri = parts
+        .uri
+        .path_and_query()
+        .cloned()
+        .map(http::Uri::from)
+        .unwrap_or_default();
+    parts.extensions = http::Extensions::new();
+    let resp = upstream
+        .call(http::Request::from_parts(parts, Body::new(body)))
+        .await
+        .map_err(|e| Status::unavailable(format!("upstream failed: {}", e.into())))?;
+    Ok(resp.map(Body::new))
+}
+
+fn status_response(status: Status) -> http::Response<Body> {
+    let (parts, ()) = status.into_http::<()>().into_parts();
+    http::Response::from_parts(parts, Body::empty())
+}
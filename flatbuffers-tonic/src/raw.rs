@@ -0,0 +1,45 @@
+use bytes::Bytes;
+use flatbuffers::InvalidFlatbuffer;
+
+use crate::{FlatBuffersCodec, OwnedFBCodecable};
+
+/// A message moved as its bytes, without verifying or decoding it, i.e. to forward it.
+/// [`RawFlatBuffers::decode`] verifies it when it is needed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawFlatBuffers(Bytes);
+
+impl RawFlatBuffers {
+    pub fn new(buf: Bytes) -> Self {
+        Self(buf)
+    }
+
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.0
+    }
+
+    /// Verify and decode the message, i.e. into its generated wrapper type.
+    /// Zero copy, the result shares the buffer.
+    pub fn decode<T: OwnedFBCodecable>(&self) -> Result<T, InvalidFlatbuffer> {
+        T::new_from_bytes(self.0.clone())
+    }
+}
+
+impl From<Bytes> for RawFlatBuffers {
+    fn from(buf: Bytes) -> Self {
+        Self(buf)
+    }
+}
+
+/// Never fails, the bytes are not verified.
+impl OwnedFBCodecable for RawFlatBuffers {
+    fn new_from_bytes(buf: Bytes) -> Result<Self, InvalidFlatbuffer> {
+        Ok(Self(buf))
+    }
+
+    fn into_bytes(self) -> Bytes {
+        self.0
+    }
+}
+
+/// Codec moving the messages of any method as [`RawFlatBuffers`].
+pub type RawFlatBuffersCodec = FlatBuffersCodec<RawFlatBuffers, RawFlatBuffers>;
@@ -0,0 +1,46 @@
+use tonic::{
+    Code, Status,
+    metadata::{MetadataMap, MetadataValue},
+};
+
+use crate::OwnedFBCodecable;
+
+/// Binary metadata key of the flatbuffer details of a status.
+/// `grpc-status-details-bin` is reserved for a `google.rpc.Status` protobuf.
+pub const DETAILS_KEY: &str = "fb-error-details-bin";
+
+/// Create a status carrying a flatbuffer table as its details.
+///
+/// The buffer is sent in the [`DETAILS_KEY`] trailer and read back with [`StatusExt::fb_details`].
+pub fn status_with_details<T: OwnedFBCodecable>(
+    code: Code,
+    message: impl Into<String>,
+    details: T,
+) -> Status {
+    let mut metadata = MetadataMap::new();
+    metadata.insert_bin(
+        DETAILS_KEY,
+        MetadataValue::from_bytes(&details.into_bytes()),
+    );
+    Status::with_metadata(code, message, metadata)
+}
+
+/// Read flatbuffer details from a [`Status`].
+pub trait StatusExt {
+    /// Verify and decode the details, `None` if the status has no details.
+    fn fb_details<T: OwnedFBCodecable>(&self) -> Option<Result<T, flatbuffers::InvalidFlatbuffer>>;
+}
+
+impl StatusExt for Status {
+    fn fb_details<T: OwnedFBCodecable>(&self) -> Option<Result<T, flatbuffers::InvalidFlatbuffer>> {
+        let value = self.metadata().get_bin(DETAILS_KEY)?;
+        Some(match value.to_bytes() {
+            Ok(bytes) => T::new_from_bytes(bytes),
+            // Not base64, none of it is in range of a flatbuffer.
+            Err(_) => Err(flatbuffers::InvalidFlatbuffer::RangeOutOfBounds {
+                range: 0..value.as_encoded_bytes().len(),
+                error_trace: Default::default(),
+            }),
+        })
+    }
+}
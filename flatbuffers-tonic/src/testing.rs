@@ -0,0 +1,351 @@
+//! Helpers for testing generated services without sockets.
+//!
+//! [`connect`] serves any generated `*Server` over `tokio::io::duplex` and returns
+//! a [`TestServer`] handle together with a client. The `Mock*` types generated with
+//! `Builder::generate_mocks` are built on these.
+
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    future::{Ready, ready},
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use hyper_util::rt::TokioIo;
+use tokio::{
+    io::DuplexStream,
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+use tokio_stream::{Stream, wrappers::UnboundedReceiverStream};
+use tonic::{
+    Request, Response, Status, Streaming,
+    body::Body,
+    server::NamedService,
+    service::Routes,
+    transport::{Channel, Endpoint, Server, Uri},
+};
+
+/// Buffer size of each in-process connection.
+const DUPLEX_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Response stream of mocked server streaming methods.
+pub type MockStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + 'static>>;
+
+type Handler<Req, Resp> = Arc<dyn Fn(&[Req]) -> Result<Vec<Resp>, Status> + Send + Sync>;
+
+struct MockState<Req, Resp> {
+    responses: VecDeque<Result<Vec<Resp>, Status>>,
+    handler: Option<Handler<Req, Resp>>,
+    requests: Vec<Vec<Req>>,
+    calls: usize,
+    expected_calls: Option<usize>,
+}
+
+/// Programmable behavior of one mocked rpc method.
+///
+/// Each call takes the next queued response, or runs the `respond_with` handler once the
+/// queue is empty, and fails with `Unimplemented` if neither is set. Streamed requests are
+/// read to the end before responding. Clones share the same state.
+pub struct MockMethod<Req, Resp> {
+    path: &'static str,
+    state: Arc<Mutex<MockState<Req, Resp>>>,
+}
+
+impl<Req, Resp> Clone for MockMethod<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path,
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<Req, Resp> MockMethod<Req, Resp> {
+    /// `path` is the gRPC path of the method, used in error messages.
+    pub fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            state: Arc::new(Mutex::new(MockState {
+                responses: VecDeque::new(),
+                handler: None,
+                requests: Vec::new(),
+                calls: 0,
+                expected_calls: None,
+            })),
+        }
+    }
+
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState<Req, Resp>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queue a response message for the next call.
+    pub fn respond(&self, response: Resp) -> &Self {
+        self.state().responses.push_back(Ok(vec![response]));
+        self
+    }
+
+    /// Queue the messages of a streamed response for the next call.
+    pub fn respond_stream(&self, responses: impl IntoIterator<Item = Resp>) -> &Self {
+        let responses = responses.into_iter().collect();
+        self.state().responses.push_back(Ok(responses));
+        self
+    }
+
+    /// Queue an error for the next call.
+    pub fn respond_err(&self, status: Status) -> &Self {
+        self.state().responses.push_back(Err(status));
+        self
+    }
+
+    /// Compute the response messages from the request messages, used once the queue is empty.
+    pub fn respond_with<F>(&self, handler: F) -> &Self
+    where
+        F: Fn(&[Req]) -> Result<Vec<Resp>, Status> + Send + Sync + 'static,
+    {
+        self.state().handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Expect exactly `n` calls, checked by [`MockMethod::verify`].
+    pub fn times(&self, n: usize) -> &Self {
+        self.state().expected_calls = Some(n);
+        self
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.state().calls
+    }
+
+    /// Take the request messages received so far, one entry per call.
+    pub fn take_requests(&self) -> Vec<Vec<Req>> {
+        std::mem::take(&mut self.state().requests)
+    }
+
+    /// Panics if the method was not called as often as expected.
+    pub fn verify(&self) {
+        let state = self.state();
+        if let Some(expected) = state.expected_calls {
+            assert_eq!(
+                state.calls, expected,
+                "{}: expected {expected} calls, got {}",
+                self.path, state.calls
+            );
+        }
+    }
+
+    fn call(&self, requests: Vec<Req>) -> Result<Vec<Resp>, Status> {
+        let (queued, handler) = {
+            let mut state = self.state();
+            state.calls += 1;
+            (state.responses.pop_front(), state.handler.clone())
+        };
+        // The handler runs without the lock so it may use the mock itself.
+        let result = match (queued, handler) {
+            (Some(result), _) => result,
+            (None, Some(handler)) => handler(&requests),
+            (None, None) => Err(Status::unimplemented(format!(
+                "no response programmed for {}",
+                self.path
+            ))),
+        };
+        self.state().requests.push(requests);
+        result
+    }
+
+    fn single(&self, mut responses: Vec<Resp>) -> Result<Response<Resp>, Status> {
+        if responses.len() != 1 {
+            return Err(Status::internal(format!(
+                "{}: expected one response message, got {}",
+                self.path,
+                responses.len()
+            )));
+        }
+        Ok(Response::new(responses.remove(0)))
+    }
+}
+
+impl<Req, Resp> MockMethod<Req, Resp>
+where
+    Req: Send + 'static,
+    Resp: Send + 'static,
+{
+    pub async fn unary(&self, request: Request<Req>) -> Result<Response<Resp>, Status> {
+        let responses = self.call(vec![request.into_inner()])?;
+        self.single(responses)
+    }
+
+    pub async fn client_streaming(
+        &self,
+        request: Request<Streaming<Req>>,
+    ) -> Result<Response<Resp>, Status> {
+        let requests = read_all(request.into_inner()).await?;
+        let responses = self.call(requests)?;
+        self.single(responses)
+    }
+
+    pub async fn server_streaming(
+        &self,
+        request: Request<Req>,
+    ) -> Result<Response<MockStream<Resp>>, Status> {
+        let responses = self.call(vec![request.into_inner()])?;
+        Ok(Response::new(into_stream(responses)))
+    }
+
+    pub async fn streaming(
+        &self,
+        request: Request<Streaming<Req>>,
+    ) -> Result<Response<MockStream<Resp>>, Status> {
+        let requests = read_all(request.into_inner()).await?;
+        let responses = self.call(requests)?;
+        Ok(Response::new(into_stream(responses)))
+    }
+}
+
+async fn read_all<T>(mut stream: Streaming<T>) -> Result<Vec<T>, Status> {
+    let mut messages = Vec::new();
+    while let Some(message) = stream.message().await? {
+        messages.push(message);
+    }
+    Ok(messages)
+}
+
+fn into_stream<T: Send + 'static>(messages: Vec<T>) -> MockStream<T> {
+    Box::pin(tokio_stream::iter(messages.into_iter().map(Ok)))
+}
+
+/// Serve the routes in process over `tokio::io::duplex` and return a channel to them.
+/// The server runs on a spawned task until the channel and its clones are dropped.
+pub fn in_process_channel(routes: Routes) -> Channel {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        // Errors surface on the client as transport errors.
+        let _ = Server::builder()
+            .add_routes(routes)
+            .serve_with_incoming(UnboundedReceiverStream::new(rx))
+            .await;
+    });
+    duplex_channel(tx)
+}
+
+/// Serve a generated `*Server` in process and build a client on a channel to it.
+///
+/// ```ignore
+/// let (server, mut client) = connect(GreeterServer::new(Greeter {}), GreeterClient::new);
+/// client.say_hello(request).await?;
+/// server.shutdown().await?;
+/// ```
+pub fn connect<S, C>(service: S, client: impl FnOnce(Channel) -> C) -> (TestServer, C)
+where
+    S: tower_service::Service<
+            http::Request<Body>,
+            Response = http::Response<Body>,
+            Error = Infallible,
+        > + NamedService
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Future: Send + 'static,
+{
+    let server = TestServer::new(Routes::new(service));
+    let client = client(server.channel());
+    (server, client)
+}
+
+/// Handle of a server running in process, see [`connect`].
+///
+/// Dropping the handle stops the server, [`TestServer::shutdown`] also waits for it.
+pub struct TestServer {
+    channel: Channel,
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<Result<(), tonic::transport::Error>>>,
+}
+
+impl TestServer {
+    /// Serve the routes, use [`Routes::add_service`] to serve several services.
+    pub fn new(routes: Routes) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            Server::builder()
+                .add_routes(routes)
+                .serve_with_incoming_shutdown(UnboundedReceiverStream::new(rx), async {
+                    let _ = signal.await;
+                })
+                .await
+        });
+        Self {
+            channel: duplex_channel(tx),
+            shutdown: Some(shutdown),
+            handle: Some(handle),
+        }
+    }
+
+    /// A new channel to the server, for additional clients.
+    pub fn channel(&self) -> Channel {
+        self.channel.clone()
+    }
+
+    /// Stop accepting connections and wait for the in-flight rpcs to finish.
+    pub async fn shutdown(mut self) -> Result<(), tonic::transport::Error> {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        match self.handle.take() {
+            Some(handle) => handle.await.expect("in-process server panicked"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+fn duplex_channel(tx: mpsc::UnboundedSender<io::Result<DuplexStream>>) -> Channel {
+    Endpoint::from_static("http://in-process").connect_with_connector_lazy(DuplexConnector { tx })
+}
+
+/// Hands the server half of a new duplex pipe to the server for every connection.
+#[derive(Clone)]
+struct DuplexConnector {
+    tx: mpsc::UnboundedSender<io::Result<DuplexStream>>,
+}
+
+impl tower_service::Service<Uri> for DuplexConnector {
+    type Response = TokioIo<DuplexStream>;
+    type Error = io::Error;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let (client, server) = tokio::io::duplex(DUPLEX_BUFFER_SIZE);
+        ready(
+            self.tx
+                .send(Ok(server))
+                .map(|_| TokioIo::new(client))
+                .map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::ConnectionRefused,
+                        "in-process server stopped",
+                    )
+                }),
+        )
+    }
+}
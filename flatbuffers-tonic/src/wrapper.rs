@@ -1,5 +1,9 @@
+use flatbuffers::{Follow, Verifiable};
+
+use crate::OwnedFB;
+
 /// trait for codec to deal with owned flatbuffer
-/// All tonic flatbuffer wrappers needs to implement this.
+/// Implemented by `OwnedFB` of any table, other message types can implement it too.
 pub trait OwnedFBCodecable {
     fn new_from_bytes(buf: bytes::Bytes) -> Result<Self, flatbuffers::InvalidFlatbuffer>
     where
@@ -7,3 +11,60 @@ pub trait OwnedFBCodecable {
 
     fn into_bytes(self) -> bytes::Bytes;
 }
+
+/// Any verifiable root, i.e. `OwnedFB<HelloRequest<'static>>` of flatc output from any crate.
+impl<T> OwnedFBCodecable for OwnedFB<T>
+where
+    T: Verifiable + Follow<'static> + 'static,
+{
+    fn new_from_bytes(buf: bytes::Bytes) -> Result<Self, flatbuffers::InvalidFlatbuffer> {
+        OwnedFB::new_from_bytes(buf)
+    }
+
+    fn into_bytes(self) -> bytes::Bytes {
+        OwnedFB::into_bytes(self)
+    }
+}
+
+/// Declare a wrapper of `OwnedFB` for a flatc table, usable as rpc message.
+/// tonic services cannot use `OwnedFB<Table<'static>>` directly: the lifetime makes
+/// their futures fail to prove `Send`. Works for flatc output of any crate.
+///
+/// ```ignore
+/// flatbuffers_tonic::owned_wrapper! {
+///     /// Owned `HelloRequest` table.
+///     pub OwnedHelloRequest(fbs::helloworld::HelloRequest)
+/// }
+/// ```
+#[macro_export]
+macro_rules! owned_wrapper {
+    ($(#[$attr:meta])* $vis:vis $name:ident($($table:ident)::+)) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        $vis struct $name(pub $crate::OwnedFB<$($table)::+<'static>>);
+
+        impl $name {
+            pub fn get_ref<'a>(&'a self) -> $($table)::+<'a> {
+                self.0.get_ref()
+            }
+        }
+
+        impl From<$crate::OwnedFB<$($table)::+<'static>>> for $name {
+            fn from(value: $crate::OwnedFB<$($table)::+<'static>>) -> Self {
+                Self(value)
+            }
+        }
+
+        impl $crate::OwnedFBCodecable for $name {
+            fn new_from_bytes(
+                buf: $crate::__private::Bytes,
+            ) -> Result<Self, $crate::__private::InvalidFlatbuffer> {
+                $crate::OwnedFBCodecable::new_from_bytes(buf).map(Self)
+            }
+
+            fn into_bytes(self) -> $crate::__private::Bytes {
+                self.0.into_bytes()
+            }
+        }
+    };
+}
@@ -2,7 +2,13 @@
 pub mod reflect;
 
 mod ownedfb;
-pub use ownedfb::OwnedFB;
+pub use ownedfb::{OwnedFB, OwnedFBMut, TableRef};
 
 mod builder;
 pub use builder::FBBuilder;
+
+/// Paths used by the macros.
+#[doc(hidden)]
+pub mod __private {
+    pub use flatbuffers::Table;
+}
@@ -1,13 +1,42 @@
-use flatbuffers::{Follow, InvalidFlatbuffer, Verifiable};
+use bytes::{Bytes, BytesMut};
+use flatbuffers::{EndianScalar, Follow, InvalidFlatbuffer, Table, VOffsetT, Verifiable};
 
 /// Stores the owned bytes of the flatbuffer type
 /// and can access the actual type.
 pub struct OwnedFB<T> {
-    buf: Vec<u8>,
+    buf: Bytes,
     index: usize,
     _phantom: std::marker::PhantomData<T>,
 }
 
+/// A flatc table, the child `OwnedFB::project` takes.
+/// Implemented with `impl_table_ref!`, the code of flatbuffers-tonic-build does
+/// for the tables of the compiled files.
+pub trait TableRef<'a> {
+    /// The table type of an `OwnedFB`, i.e. `Shape<'static>`.
+    type Static: Verifiable + Follow<'static> + 'static;
+
+    fn table(&self) -> Table<'a>;
+}
+
+/// Implement `TableRef` for flatc tables, in the crate including the flatc output.
+///
+/// ```ignore
+/// flatbuffers_util::impl_table_ref!(fbs::objects::Shape);
+/// ```
+#[macro_export]
+macro_rules! impl_table_ref {
+    ($($table:ident)::+) => {
+        impl<'a> $crate::TableRef<'a> for $($table)::+<'a> {
+            type Static = $($table)::+<'static>;
+
+            fn table(&self) -> $crate::__private::Table<'a> {
+                self._tab
+            }
+        }
+    };
+}
+
 impl<T> OwnedFB<T> {
     pub fn new<'a>(buf: &'a [u8]) -> Result<OwnedFB<T>, InvalidFlatbuffer>
     where
@@ -20,6 +49,12 @@ impl<T> OwnedFB<T> {
     /// # Safety
     /// Caller is responsible for verifying the buffer and align the type T.
     pub unsafe fn new_from_vec_unchecked(buf: Vec<u8>, index: usize) -> Self {
+        unsafe { Self::new_from_bytes_unchecked(buf.into(), index) }
+    }
+
+    /// # Safety
+    /// Caller is responsible for verifying the buffer and align the type T.
+    unsafe fn new_from_bytes_unchecked(buf: Bytes, index: usize) -> Self {
         Self {
             buf,
             index,
@@ -42,25 +77,13 @@ impl<T> OwnedFB<T> {
         Ok(unsafe { Self::new_from_vec_unchecked(buf, index) })
     }
 
-    /// This in practice is not zero copy.
-    /// For tonic, the bytes is always shared BytesMut, so it cannot be directly turned into vec.
-    /// And in general Bytes have multiple chunks, and flatbuffer need contiguous memory.
-    /// So this will make a copy in most cases.
+    /// Zero copy, the buffer is shared with `buf`.
     pub fn new_from_bytes(buf: bytes::Bytes) -> Result<OwnedFB<T>, InvalidFlatbuffer>
     where
         T: Verifiable + Follow<'static> + 'static,
     {
-        match buf.try_into_mut() {
-            // This is zero copy if the Bytes has the full ownership of the vec.
-            Ok(bytes_mut) => Self::new_from_vec(bytes_mut.into(), 0),
-            // This will make a copy of the bytes.
-            Err(bytes) => {
-                // This happens in tonic DecodeBuf because it is a shared BytesMut.
-                // So in practice tonic forces us to make a copy here.
-                // debug_assert!(false, "zero copy failed.");
-                Self::new_from_vec(bytes.to_vec(), 0)
-            }
-        }
+        check_flatbuffer::<T>(&buf, 0)?;
+        Ok(unsafe { Self::new_from_bytes_unchecked(buf, 0) })
     }
 
     pub fn get_ref<'a>(&'a self) -> <T as Follow<'a>>::Inner
@@ -75,13 +98,103 @@ impl<T> OwnedFB<T> {
         &self.buf[self.index..]
     }
 
-    /// This may be zero copy if the vec capacity equals to length and index is zero.
+    /// Get a mutable accessor to edit scalar fields of the root table in place.
+    /// The buffer is copied first if it is shared, i.e. with a clone or projection.
+    pub fn get_mut(&mut self) -> OwnedFBMut<'_, T> {
+        OwnedFBMut { owned: self }
+    }
+
+    /// Zero copy, the returned bytes share the buffer.
     pub fn into_bytes(self) -> bytes::Bytes {
-        // This is zero copy if vec cap == len.
-        debug_assert_eq!(self.buf.capacity(), self.buf.len());
-        let full_bytes = bytes::Bytes::from(self.buf);
-        // adjust the offset should be zero copy.
-        full_bytes.slice(self.index..)
+        self.buf.slice(self.index..)
+    }
+
+    /// A table nested in this one as its own flatbuffer, i.e. to forward or cache it.
+    /// `f` returns the table, i.e. `|root| root.child()`, `Ok(None)` if it returns `None`.
+    ///
+    /// The result shares the buffer, starting at an offset in front of the table that points
+    /// to it, usually the field of the parent. The table is copied into a new buffer
+    /// if no such offset makes a valid flatbuffer. A table of another buffer is
+    /// `InvalidFlatbuffer::RangeOutOfBounds`.
+    pub fn project<'a, C>(
+        &'a self,
+        f: impl FnOnce(<T as Follow<'a>>::Inner) -> Option<C>,
+    ) -> Result<Option<OwnedFB<C::Static>>, InvalidFlatbuffer>
+    where
+        T: Follow<'a>,
+        C: TableRef<'a>,
+    {
+        let Some(child) = f(self.get_ref()) else {
+            return Ok(None);
+        };
+        let table = child.table();
+        let loc = table.loc();
+        if !std::ptr::eq(table.buf(), &self.buf[..]) {
+            return Err(InvalidFlatbuffer::RangeOutOfBounds {
+                range: loc..loc,
+                error_trace: Default::default(),
+            });
+        }
+        // Offsets only point forward, so the root offset is in front of the table.
+        let mut pos = loc;
+        while pos >= self.index + 4 {
+            pos -= 4;
+            let offset = u32::from_le_bytes(self.buf[pos..pos + 4].try_into().unwrap());
+            if offset as usize == loc - pos {
+                let buf = self.buf.slice(pos..);
+                if check_flatbuffer::<C::Static>(&buf, 0).is_ok() {
+                    return Ok(Some(unsafe { OwnedFB::new_from_bytes_unchecked(buf, 0) }));
+                }
+            }
+        }
+        let mut buf = self.buf[self.index..].to_vec();
+        buf[..4].copy_from_slice(&((loc - self.index) as u32).to_le_bytes());
+        OwnedFB::new_from_vec(buf, 0).map(Some)
+    }
+}
+
+/// Shares the buffer, i.e. to resend a message.
+impl<T> Clone for OwnedFB<T> {
+    fn clone(&self) -> Self {
+        unsafe { Self::new_from_bytes_unchecked(self.buf.clone(), self.index) }
+    }
+}
+
+/// Mutable accessor of the root table of an OwnedFB.
+/// Rust flatc output has no mutators, so fields are addressed by the generated
+/// vtable slot constants, i.e. `HelloRequest::VT_NAME`.
+pub struct OwnedFBMut<'a, T> {
+    owned: &'a mut OwnedFB<T>,
+}
+
+impl<T> OwnedFBMut<'_, T> {
+    /// Overwrite the scalar field at vtable `slot` of the root table.
+    /// Returns false if the field is not present in the buffer (i.e. it was
+    /// left to its default value by the builder), same as flatc `mutate_*`.
+    ///
+    /// # Safety
+    /// Caller is responsible for the field at `slot` being a scalar of type S.
+    pub unsafe fn set_scalar<S: EndianScalar>(&mut self, slot: VOffsetT, value: S) -> bool {
+        let buf = &self.owned.buf;
+        let index = self.owned.index;
+        // Safety: The buffer is verified, so the root offset and vtable are valid.
+        let field_loc = unsafe {
+            let table = <flatbuffers::ForwardsUOffset<flatbuffers::Table>>::follow(buf, index);
+            match table.vtable().get(slot) {
+                0 => return false,
+                o => table.loc() + o as usize,
+            }
+        };
+        if field_loc + std::mem::size_of::<S>() > buf.len() {
+            return false;
+        }
+        let mut buf = match std::mem::take(&mut self.owned.buf).try_into_mut() {
+            Ok(buf) => buf,
+            Err(shared) => BytesMut::from(&shared[..]),
+        };
+        unsafe { flatbuffers::emplace_scalar(&mut buf[field_loc..], value) };
+        self.owned.buf = buf.freeze();
+        true
     }
 }
 
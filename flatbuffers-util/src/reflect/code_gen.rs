@@ -1,22 +1,86 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use flatbuffers::{ForwardsUOffset, Vector};
 use flatbuffers_reflection::reflection;
 
+use super::{
+    dynamic::DynamicError,
+    model::{EnumDef, ObjectDef, split_name},
+};
+
 fn get_services_from_schema(schema: &reflection::Schema) -> Vec<Service> {
     schema
         .services()
-        .unwrap()
         .iter()
+        .flatten()
         .map(|service| Service::new_from_schema(&service))
         .collect()
 }
 
+#[derive(Debug)]
 pub struct GeneratorContext {
     pub services: Vec<Service>,
+    /// All tables and structs, including those of included files
+    pub objects: Vec<ObjectDef>,
+    /// All enums and unions, including those of included files
+    pub enums: Vec<EnumDef>,
+    /// The compiled file, the one no other file of the schema includes.
+    /// `None` without the file names flatc stores with `--bfbs-filenames`.
+    pub root_file: Option<String>,
 }
 
 impl GeneratorContext {
-    pub fn parse_from_schema(schema: &reflection::Schema) -> Self {
+    /// Fails if a type index of the schema is out of range.
+    pub fn parse_from_schema(schema: &reflection::Schema) -> Result<Self, DynamicError> {
         let services = get_services_from_schema(schema);
-        GeneratorContext { services }
+        let objects = schema
+            .objects()
+            .iter()
+            .map(|object| ObjectDef::new(schema, &object))
+            .collect::<Result<_, _>>()?;
+        let enums = schema
+            .enums()
+            .iter()
+            .map(|e| EnumDef::new(schema, &e))
+            .collect::<Result<_, _>>()?;
+        Ok(GeneratorContext {
+            services,
+            objects,
+            enums,
+            root_file: root_file(schema),
+        })
+    }
+
+    /// Add the services and types of another file of the same namespace.
+    pub fn merge(&mut self, other: GeneratorContext) {
+        self.services.extend(other.services);
+        for object in other.objects {
+            if self.object(&object.name).is_none() {
+                self.objects.push(object);
+            }
+        }
+        for e in other.enums {
+            if self.enum_def(&e.name).is_none() {
+                self.enums.push(e);
+            }
+        }
+    }
+
+    /// Table or struct by fully qualified name.
+    pub fn object(&self, name: &str) -> Option<&ObjectDef> {
+        self.objects.iter().find(|object| object.name == name)
+    }
+
+    /// Enum or union by fully qualified name.
+    pub fn enum_def(&self, name: &str) -> Option<&EnumDef> {
+        self.enums.iter().find(|e| e.name == name)
+    }
+
+    /// The `///` comment lines of a table, empty if it has none.
+    pub fn documentation(&self, t: &MessageType) -> &[String] {
+        self.object(&t.full_name)
+            .map(|object| object.documentation.as_slice())
+            .unwrap_or_default()
     }
 
     pub fn get_services(&self) -> &[Service] {
@@ -29,65 +93,246 @@ impl GeneratorContext {
 
     /// get the namespace in raw form
     /// useful for creating files.
+    /// Without services it is the one of the types the compiled file declares.
+    /// Empty if the schema has no namespace.
     pub fn get_namespace(&self) -> String {
-        assert_ne!(self.services.len(), 0, "no services found");
-        self.services[0].namespace.as_ref().unwrap().clone()
+        match self.services.first() {
+            Some(service) => service.namespace.clone().unwrap_or_default(),
+            None => self
+                .declared_namespaces()
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Namespaces of the tables, structs and enums the compiled file declares,
+    /// of all of them without a root file.
+    pub fn declared_namespaces(&self) -> BTreeSet<String> {
+        let declared = |file: &Option<String>| self.root_file.is_none() || *file == self.root_file;
+        let objects = self
+            .objects
+            .iter()
+            .filter(|object| declared(&object.declaration_file))
+            .map(|object| object.namespace());
+        let enums = self
+            .enums
+            .iter()
+            .filter(|e| declared(&e.declaration_file))
+            .map(|e| e.namespace());
+        objects
+            .chain(enums)
+            .map(|namespace| namespace.unwrap_or_default().to_string())
+            .collect()
     }
 
-    /// replace dot with double colon
-    /// and to lowercase.
+    /// The rust mod path of the namespace as flatc generates it, i.e. `my_game::sample`.
     /// This is the rust mod path to be used for accessing
     /// the flatbuffers generated code from the wrapper types.
+    /// Empty if the schema has no namespace, flatc then generates the types at the root.
     pub fn get_namespace_rs(&self) -> String {
-        self.get_namespace().replace('.', "::").to_lowercase()
+        let namespace = self.get_namespace();
+        if namespace.is_empty() {
+            return namespace;
+        }
+        namespace_rs(&namespace)
     }
 }
 
+/// The compiled file, the one no other file of the schema includes.
+fn root_file(schema: &reflection::Schema) -> Option<String> {
+    let files = schema.fbs_files()?;
+    let included: Vec<&str> = files
+        .iter()
+        .flat_map(|f| f.included_filenames().unwrap_or_default())
+        .collect();
+    files
+        .iter()
+        .map(|f| f.filename())
+        .find(|name| !included.contains(name))
+        .map(|name| name.trim_start_matches("//").to_string())
+}
+
+/// Convert a schema namespace to the rust mod path flatc generates for it,
+/// i.e. `MyGame.Sample` becomes `my_game::sample`.
+/// Each part is escaped if it is a keyword, then converted to snake case, as in flatc.
+pub fn namespace_rs(namespace: &str) -> String {
+    namespace
+        .split('.')
+        .map(|part| {
+            if FLATC_RUST_KEYWORDS.contains(&part) {
+                flatc_camel_to_snake(&format!("{part}_"))
+            } else {
+                flatc_camel_to_snake(part)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Port of flatc `CamelToSnake`, which differs from heck on acronyms and digits:
+/// `HTTPServer` becomes `httpserver` and `Sample2` becomes `sample_2`.
+fn flatc_camel_to_snake(input: &str) -> String {
+    let mut s = String::new();
+    let mut prev: Option<char> = None;
+    for c in input.chars() {
+        match prev {
+            None => s.push(c.to_ascii_lowercase()),
+            Some(_) if c == '_' => s.push('_'),
+            Some(p) if !c.is_ascii_lowercase() => {
+                if p.is_ascii_lowercase() || (p.is_ascii_digit() && !c.is_ascii_digit()) {
+                    s.push('_');
+                }
+                s.push(c.to_ascii_lowercase());
+            }
+            Some(_) => s.push(c),
+        }
+        prev = Some(c);
+    }
+    s
+}
+
+/// Names flatc escapes with a `_` suffix in rust code, from `RustKeywords` in idl_gen_rust.cpp.
+const FLATC_RUST_KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "const",
+    "continue",
+    "crate",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "fn",
+    "for",
+    "if",
+    "impl",
+    "in",
+    "let",
+    "loop",
+    "match",
+    "mod",
+    "move",
+    "mut",
+    "pub",
+    "ref",
+    "return",
+    "Self",
+    "self",
+    "static",
+    "struct",
+    "super",
+    "trait",
+    "true",
+    "type",
+    "unsafe",
+    "use",
+    "where",
+    "while",
+    "abstract",
+    "alignof",
+    "become",
+    "box",
+    "do",
+    "final",
+    "macro",
+    "offsetof",
+    "override",
+    "priv",
+    "proc",
+    "pure",
+    "sizeof",
+    "typeof",
+    "unsized",
+    "virtual",
+    "yield",
+    "std",
+    "usize",
+    "isize",
+    "u8",
+    "i8",
+    "u16",
+    "i16",
+    "u32",
+    "i32",
+    "u64",
+    "i64",
+    "u128",
+    "i128",
+    "f32",
+    "f64",
+    "follow",
+    "push",
+    "size",
+    "alignment",
+    "to_little_endian",
+    "from_little_endian",
+    "ENUM_MAX",
+    "ENUM_MIN",
+    "ENUM_VALUES",
+];
+
 #[derive(Debug, Clone)]
 pub struct MessageType {
     /// type without namespace
     pub fb_type: String,
+    /// namespace of the type, which can differ from the one of the service
     pub namespace: Option<String>,
+    /// fully qualified type name, i.e. `sample.sample_request`
+    pub full_name: String,
 }
 
-/// Return all unique in/out types from services
+impl MessageType {
+    pub fn new(full_name: &str) -> Self {
+        let (namespace, fb_type) = split_name(full_name);
+        MessageType {
+            fb_type: fb_type.to_string(),
+            namespace: namespace.map(str::to_string),
+            full_name: full_name.to_string(),
+        }
+    }
+}
+
+/// Return all unique in/out types from services, by fully qualified name
 pub fn collect_in_out_types(services: &[Service]) -> Vec<MessageType> {
-    let mut types = Vec::new();
+    let mut types: Vec<MessageType> = Vec::new();
     for svc in services {
         for method in &svc.methods {
-            let req_type = method.request_type();
-            let resp_type = method.response_type();
-            if !types
-                .iter()
-                .map(|t: &MessageType| &t.fb_type)
-                .any(|x| x == &req_type)
-            {
-                types.push(MessageType {
-                    fb_type: req_type,
-                    namespace: svc.namespace.clone(),
-                });
-            }
-            if !types
-                .iter()
-                .map(|t: &MessageType| &t.fb_type)
-                .any(|x| x == &resp_type)
-            {
-                types.push(MessageType {
-                    fb_type: resp_type,
-                    namespace: svc.namespace.clone(),
-                });
+            let names = [
+                Some(&method.request_name),
+                Some(&method.response_name),
+                method.error_name.as_ref(),
+            ];
+            for name in names.into_iter().flatten() {
+                let t = MessageType::new(&qualify(name, svc.namespace.as_deref()));
+                if !types.iter().any(|x| x.full_name == t.full_name) {
+                    types.push(t);
+                }
             }
         }
     }
     types
 }
 
+/// The `error` attribute may name the type without namespace,
+/// it then refers to the namespace of the service.
+fn qualify(name: &str, namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) if !name.contains('.') => format!("{namespace}.{name}"),
+        _ => name.to_string(),
+    }
+}
+
 /// rpc Service
 #[derive(Debug)]
 pub struct Service {
     pub namespace: Option<String>,
     pub name: String,
     pub methods: Vec<Method>,
+    /// All attributes, attributes without a value map to "0" as in flatc
+    pub attributes: BTreeMap<String, String>,
+    /// `///` comment lines, without the slashes
+    pub documentation: Vec<String>,
 }
 /// rpc Method
 #[derive(Debug)]
@@ -97,8 +342,40 @@ pub struct Method {
     pub request_name: String,
     /// Unparsed response type name
     pub response_name: String,
+    /// Unparsed error details type name, from the `error` attribute
+    pub error_name: Option<String>,
+    /// Unparsed deadline, from the `timeout` attribute, i.e. `2s`
+    pub timeout: Option<String>,
+    /// Set by the `idempotent` attribute
+    pub idempotent: bool,
+    /// Unparsed retry count, from the `retry` attribute
+    pub retry: Option<String>,
     pub server_streaming: bool,
     pub client_streaming: bool,
+    /// All attributes, attributes without a value map to "0" as in flatc
+    pub attributes: BTreeMap<String, String>,
+    /// `///` comment lines, without the slashes
+    pub documentation: Vec<String>,
+}
+
+pub(crate) fn attributes_map(
+    attributes: Option<Vector<'_, ForwardsUOffset<reflection::KeyValue<'_>>>>,
+) -> BTreeMap<String, String> {
+    attributes
+        .unwrap_or_default()
+        .iter()
+        .map(|kv| (kv.key().to_string(), kv.value().unwrap_or("").to_string()))
+        .collect()
+}
+
+pub(crate) fn documentation_lines(
+    documentation: Option<Vector<'_, ForwardsUOffset<&str>>>,
+) -> Vec<String> {
+    documentation
+        .unwrap_or_default()
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
 }
 
 impl Service {
@@ -122,6 +399,8 @@ impl Service {
             namespace,
             name,
             methods,
+            attributes: attributes_map(schema.attributes()),
+            documentation: documentation_lines(schema.documentation()),
         }
     }
 
@@ -130,6 +409,12 @@ impl Service {
         let mut parts = self.name.rsplitn(2, '.');
         parts.next().unwrap().to_string()
     }
+
+    /// Fully qualified name of a type of its methods, which the `error` attribute
+    /// may name without namespace.
+    pub fn qualified_type(&self, name: &str) -> String {
+        qualify(name, self.namespace.as_deref())
+    }
 }
 
 impl Method {
@@ -137,20 +422,27 @@ impl Method {
         let name = call.name().to_string();
         let request_type = call.request().name().to_string();
         let response_type = call.response().name().to_string();
-        let streaming_type = call
-            .attributes()
-            .unwrap_or_default()
-            .iter()
-            .find(|kv| kv.key() == "streaming")
-            .and_then(|kv| kv.value());
+        let attributes = attributes_map(call.attributes());
+        let attribute = |key: &str| attributes.get(key).map(|v| v.as_str());
+        let streaming_type = attribute("streaming");
+        let error_name = attribute("error").map(|v| v.to_string());
+        let timeout = attribute("timeout").map(|v| v.to_string());
+        let idempotent = attributes.contains_key("idempotent");
+        let retry = attribute("retry").map(|v| v.to_string());
         let server_streaming = streaming_type == Some("server") || streaming_type == Some("bidi");
         let client_streaming = streaming_type == Some("client") || streaming_type == Some("bidi");
         Method {
             name,
             request_name: request_type,
             response_name: response_type,
+            error_name,
+            timeout,
+            idempotent,
+            retry,
             server_streaming,
             client_streaming,
+            attributes,
+            documentation: documentation_lines(call.documentation()),
         }
     }
 
@@ -169,4 +461,11 @@ impl Method {
         // find the last part after the last dot
         self.response_name.rsplit('.').next().unwrap().to_string()
     }
+
+    /// The error details type without namespace, if the method declares one
+    pub fn error_type(&self) -> Option<String> {
+        self.error_name
+            .as_ref()
+            .map(|name| name.rsplit('.').next().unwrap().to_string())
+    }
 }
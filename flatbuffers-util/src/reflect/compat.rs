@@ -0,0 +1,695 @@
+use std::fmt;
+
+use flatbuffers_reflection::reflection::{BaseType, RPCCall, Schema, Type};
+
+use super::verify::{enum_at, object_at};
+
+/// A change between two versions of a schema that breaks peers built from the old one.
+///
+/// Tables and fields are matched by fully qualified name, fields also by id,
+/// so renaming a field while keeping its id and type is compatible on the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakingChange {
+    ServiceRemoved {
+        service: String,
+    },
+    RpcRemoved {
+        service: String,
+        method: String,
+    },
+    StreamingChanged {
+        service: String,
+        method: String,
+        old: String,
+        new: String,
+    },
+    RequestTypeChanged {
+        service: String,
+        method: String,
+        old: String,
+        new: String,
+    },
+    ResponseTypeChanged {
+        service: String,
+        method: String,
+        old: String,
+        new: String,
+    },
+    TableRemoved {
+        table: String,
+    },
+    /// Struct layout changed, or a struct became a table or the other way around.
+    StructChanged {
+        name: String,
+    },
+    FieldRemoved {
+        table: String,
+        field: String,
+    },
+    FieldIdChanged {
+        table: String,
+        field: String,
+        old: u16,
+        new: u16,
+    },
+    FieldTypeChanged {
+        table: String,
+        field: String,
+        old: String,
+        new: String,
+    },
+    /// Old readers reject messages without a required field, new ones those of old writers.
+    FieldRequiredChanged {
+        table: String,
+        field: String,
+        required: bool,
+    },
+    EnumRemoved {
+        name: String,
+    },
+    EnumTypeChanged {
+        name: String,
+        old: String,
+        new: String,
+    },
+    EnumValueRemoved {
+        name: String,
+        value: String,
+    },
+    EnumValueChanged {
+        name: String,
+        value: String,
+        old: i64,
+        new: i64,
+    },
+    UnionVariantChanged {
+        name: String,
+        value: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl fmt::Display for BreakingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakingChange::ServiceRemoved { service } => {
+                write!(f, "service {service} was removed")
+            }
+            BreakingChange::RpcRemoved { service, method } => {
+                write!(f, "rpc {service}.{method} was removed")
+            }
+            BreakingChange::StreamingChanged {
+                service,
+                method,
+                old,
+                new,
+            } => write!(
+                f,
+                "rpc {service}.{method} streaming changed from {old} to {new}"
+            ),
+            BreakingChange::RequestTypeChanged {
+                service,
+                method,
+                old,
+                new,
+            } => write!(
+                f,
+                "rpc {service}.{method} request changed from {old} to {new}"
+            ),
+            BreakingChange::ResponseTypeChanged {
+                service,
+                method,
+                old,
+                new,
+            } => write!(
+                f,
+                "rpc {service}.{method} response changed from {old} to {new}"
+            ),
+            BreakingChange::TableRemoved { table } => write!(f, "table {table} was removed"),
+            BreakingChange::StructChanged { name } => write!(f, "struct {name} changed layout"),
+            BreakingChange::FieldRemoved { table, field } => {
+                write!(f, "field {table}.{field} was removed")
+            }
+            BreakingChange::FieldIdChanged {
+                table,
+                field,
+                old,
+                new,
+            } => write!(f, "field {table}.{field} id changed from {old} to {new}"),
+            BreakingChange::FieldTypeChanged {
+                table,
+                field,
+                old,
+                new,
+            } => write!(f, "field {table}.{field} type changed from {old} to {new}"),
+            BreakingChange::FieldRequiredChanged {
+                table,
+                field,
+                required: true,
+            } => write!(f, "field {table}.{field} became required"),
+            BreakingChange::FieldRequiredChanged { table, field, .. } => {
+                write!(f, "field {table}.{field} is no longer required")
+            }
+            BreakingChange::EnumRemoved { name } => write!(f, "enum {name} was removed"),
+            BreakingChange::EnumTypeChanged { name, old, new } => {
+                write!(f, "enum {name} type changed from {old} to {new}")
+            }
+            BreakingChange::EnumValueRemoved { name, value } => {
+                write!(f, "enum value {name}.{value} was removed")
+            }
+            BreakingChange::EnumValueChanged {
+                name,
+                value,
+                old,
+                new,
+            } => write!(f, "enum value {name}.{value} changed from {old} to {new}"),
+            BreakingChange::UnionVariantChanged {
+                name,
+                value,
+                old,
+                new,
+            } => write!(
+                f,
+                "union variant {name}.{value} changed from {old} to {new}"
+            ),
+        }
+    }
+}
+
+/// Compare an old and a new version of a schema and return the changes that break
+/// wire compatibility between them, empty if the new schema is compatible.
+///
+/// Adding services, rpcs, tables, fields and enum values is compatible, as is
+/// deprecating a field or changing a scalar to an enum of the same type.
+/// Changed default values are not detected: they are not on the wire, but readers
+/// of either version see a different value for fields that were left unset.
+///
+/// Schemas are compared by name, so both should be compiled from the same root file,
+/// i.e. with [`compile_reflection_schema`](super::compile_reflection_schema).
+pub fn check_compatibility(old: &Schema, new: &Schema) -> Vec<BreakingChange> {
+    Snapshot::from_schema(old).breaking_changes(&Snapshot::from_schema(new))
+}
+
+/// The wire relevant surface of a schema: services, rpcs, table fields, struct
+/// layouts and enum values.
+///
+/// A snapshot can be stored as text and compared with a later version of the
+/// schema, like a lock file. The text is line based and stable, so it diffs well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    services: Vec<ServiceSnapshot>,
+    objects: Vec<ObjectSnapshot>,
+    enums: Vec<EnumSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ServiceSnapshot {
+    name: String,
+    rpcs: Vec<RpcSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RpcSnapshot {
+    name: String,
+    request: String,
+    response: String,
+    streaming: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ObjectSnapshot {
+    name: String,
+    is_struct: bool,
+    bytesize: i32,
+    fields: Vec<FieldSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FieldSnapshot {
+    id: u16,
+    name: String,
+    type_name: String,
+    /// Byte offset in a struct, vtable offset in a table.
+    offset: u16,
+    deprecated: bool,
+    required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EnumSnapshot {
+    name: String,
+    underlying: String,
+    values: Vec<EnumValueSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EnumValueSnapshot {
+    name: String,
+    value: i64,
+    /// The table of a union variant.
+    union_type: Option<String>,
+}
+
+impl Snapshot {
+    pub fn from_schema(schema: &Schema) -> Self {
+        let services = schema
+            .services()
+            .unwrap_or_default()
+            .iter()
+            .map(|service| ServiceSnapshot {
+                name: service.name().to_string(),
+                rpcs: service
+                    .calls()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|call| RpcSnapshot {
+                        name: call.name().to_string(),
+                        request: call.request().name().to_string(),
+                        response: call.response().name().to_string(),
+                        streaming: streaming(&call).to_string(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let objects = schema
+            .objects()
+            .iter()
+            .map(|object| ObjectSnapshot {
+                name: object.name().to_string(),
+                is_struct: object.is_struct(),
+                bytesize: object.bytesize(),
+                fields: object
+                    .fields()
+                    .iter()
+                    .map(|field| FieldSnapshot {
+                        id: field.id(),
+                        name: field.name().to_string(),
+                        type_name: type_name(schema, &field.type_()),
+                        offset: field.offset(),
+                        deprecated: field.deprecated(),
+                        required: field.required(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let enums = schema
+            .enums()
+            .iter()
+            .map(|e| EnumSnapshot {
+                name: e.name().to_string(),
+                underlying: base_type_name(e.underlying_type().base_type()),
+                values: e
+                    .values()
+                    .iter()
+                    .map(|v| EnumValueSnapshot {
+                        name: v.name().to_string(),
+                        value: v.value(),
+                        union_type: v
+                            .union_type()
+                            .filter(|ty| ty.base_type() == BaseType::Obj)
+                            .map(|ty| type_name(schema, &ty)),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Snapshot {
+            services,
+            objects,
+            enums,
+        }
+    }
+
+    /// Parse a snapshot written with `to_string()`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut snapshot = Snapshot {
+            services: Vec::new(),
+            objects: Vec::new(),
+            enums: Vec::new(),
+        };
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            parse_line(&mut snapshot, &tokens)
+                .ok_or_else(|| format!("invalid snapshot line {}: {line}", line_no + 1))?;
+        }
+        Ok(snapshot)
+    }
+
+    /// Changes from this, the old version, to `new` that break wire compatibility.
+    pub fn breaking_changes(&self, new: &Snapshot) -> Vec<BreakingChange> {
+        let mut changes = Vec::new();
+        for old_service in &self.services {
+            match new.services.iter().find(|s| s.name == old_service.name) {
+                Some(new_service) => check_service(old_service, new_service, &mut changes),
+                None => changes.push(BreakingChange::ServiceRemoved {
+                    service: old_service.name.clone(),
+                }),
+            }
+        }
+        for old_object in &self.objects {
+            match new.objects.iter().find(|o| o.name == old_object.name) {
+                Some(new_object) => check_object(self, new, old_object, new_object, &mut changes),
+                None => changes.push(BreakingChange::TableRemoved {
+                    table: old_object.name.clone(),
+                }),
+            }
+        }
+        for old_enum in &self.enums {
+            match new.enums.iter().find(|e| e.name == old_enum.name) {
+                Some(new_enum) => check_enum(old_enum, new_enum, &mut changes),
+                None => changes.push(BreakingChange::EnumRemoved {
+                    name: old_enum.name.clone(),
+                }),
+            }
+        }
+        changes
+    }
+}
+
+fn parse_line(snapshot: &mut Snapshot, tokens: &[&str]) -> Option<()> {
+    match tokens {
+        ["service", name] => snapshot.services.push(ServiceSnapshot {
+            name: name.to_string(),
+            rpcs: Vec::new(),
+        }),
+        ["rpc", name, request, response, streaming] => {
+            snapshot.services.last_mut()?.rpcs.push(RpcSnapshot {
+                name: name.to_string(),
+                request: request.to_string(),
+                response: response.to_string(),
+                streaming: streaming.to_string(),
+            })
+        }
+        [kind @ ("table" | "struct"), name, bytesize] => snapshot.objects.push(ObjectSnapshot {
+            name: name.to_string(),
+            is_struct: *kind == "struct",
+            bytesize: bytesize.parse().ok()?,
+            fields: Vec::new(),
+        }),
+        ["field", id, name, type_name, offset, flags @ ..] => {
+            let mut field = FieldSnapshot {
+                id: id.parse().ok()?,
+                name: name.to_string(),
+                type_name: type_name.to_string(),
+                offset: offset.strip_prefix('@')?.parse().ok()?,
+                deprecated: false,
+                required: false,
+            };
+            for flag in flags {
+                match *flag {
+                    "deprecated" => field.deprecated = true,
+                    "required" => field.required = true,
+                    _ => return None,
+                }
+            }
+            snapshot.objects.last_mut()?.fields.push(field)
+        }
+        ["enum", name, underlying] => snapshot.enums.push(EnumSnapshot {
+            name: name.to_string(),
+            underlying: underlying.to_string(),
+            values: Vec::new(),
+        }),
+        ["value", name, value, union_type @ ..] => {
+            snapshot.enums.last_mut()?.values.push(EnumValueSnapshot {
+                name: name.to_string(),
+                value: value.parse().ok()?,
+                union_type: match union_type {
+                    [] => None,
+                    [union_type] => Some(union_type.to_string()),
+                    _ => return None,
+                },
+            })
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for service in &self.services {
+            writeln!(f, "service {}", service.name)?;
+            for rpc in &service.rpcs {
+                writeln!(
+                    f,
+                    "  rpc {} {} {} {}",
+                    rpc.name, rpc.request, rpc.response, rpc.streaming
+                )?;
+            }
+        }
+        for object in &self.objects {
+            let kind = if object.is_struct { "struct" } else { "table" };
+            writeln!(f, "{kind} {} {}", object.name, object.bytesize)?;
+            for field in &object.fields {
+                write!(
+                    f,
+                    "  field {} {} {} @{}",
+                    field.id, field.name, field.type_name, field.offset
+                )?;
+                if field.deprecated {
+                    write!(f, " deprecated")?;
+                }
+                if field.required {
+                    write!(f, " required")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        for e in &self.enums {
+            writeln!(f, "enum {} {}", e.name, e.underlying)?;
+            for value in &e.values {
+                write!(f, "  value {} {}", value.name, value.value)?;
+                if let Some(union_type) = &value.union_type {
+                    write!(f, " {union_type}")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_service(old: &ServiceSnapshot, new: &ServiceSnapshot, changes: &mut Vec<BreakingChange>) {
+    let service = &old.name;
+    for old_rpc in &old.rpcs {
+        let Some(new_rpc) = new.rpcs.iter().find(|r| r.name == old_rpc.name) else {
+            changes.push(BreakingChange::RpcRemoved {
+                service: service.clone(),
+                method: old_rpc.name.clone(),
+            });
+            continue;
+        };
+        if old_rpc.streaming != new_rpc.streaming {
+            changes.push(BreakingChange::StreamingChanged {
+                service: service.clone(),
+                method: old_rpc.name.clone(),
+                old: old_rpc.streaming.clone(),
+                new: new_rpc.streaming.clone(),
+            });
+        }
+        if old_rpc.request != new_rpc.request {
+            changes.push(BreakingChange::RequestTypeChanged {
+                service: service.clone(),
+                method: old_rpc.name.clone(),
+                old: old_rpc.request.clone(),
+                new: new_rpc.request.clone(),
+            });
+        }
+        if old_rpc.response != new_rpc.response {
+            changes.push(BreakingChange::ResponseTypeChanged {
+                service: service.clone(),
+                method: old_rpc.name.clone(),
+                old: old_rpc.response.clone(),
+                new: new_rpc.response.clone(),
+            });
+        }
+    }
+}
+
+/// Streaming kind of an rpc, `none` if not set.
+fn streaming<'a>(call: &RPCCall<'a>) -> &'a str {
+    call.attributes()
+        .unwrap_or_default()
+        .iter()
+        .find(|kv| kv.key() == "streaming")
+        .and_then(|kv| kv.value())
+        .unwrap_or("none")
+}
+
+/// Type of a field on the wire, an enum is its underlying type,
+/// i.e. `[byte]` for `[sample.Color]`.
+fn wire_type(types: &Snapshot, type_name: &str) -> String {
+    let (element, suffix) = match type_name
+        .strip_prefix('[')
+        .and_then(|t| t.strip_suffix(']'))
+    {
+        Some(inner) => match inner.split_once(':') {
+            Some((element, len)) => (element, format!(":{len}]")),
+            None => (inner, "]".to_string()),
+        },
+        None => {
+            return enum_underlying(types, type_name)
+                .unwrap_or(type_name)
+                .to_string();
+        }
+    };
+    let element = enum_underlying(types, element).unwrap_or(element);
+    format!("[{element}{suffix}")
+}
+
+/// Underlying type of an enum, unions keep their name.
+fn enum_underlying<'a>(types: &'a Snapshot, name: &str) -> Option<&'a str> {
+    types
+        .enums
+        .iter()
+        .find(|e| e.name == name && e.underlying != "utype")
+        .map(|e| e.underlying.as_str())
+}
+
+fn check_object(
+    old_types: &Snapshot,
+    new_types: &Snapshot,
+    old: &ObjectSnapshot,
+    new: &ObjectSnapshot,
+    changes: &mut Vec<BreakingChange>,
+) {
+    let name = &old.name;
+    // A changed enum type is reported with the enum.
+    let same_type = |old_field: &FieldSnapshot, new_field: &FieldSnapshot| {
+        old_field.type_name == new_field.type_name
+            || wire_type(old_types, &old_field.type_name)
+                == wire_type(new_types, &new_field.type_name)
+    };
+    if old.is_struct || new.is_struct {
+        // Structs are inline, any change to their fields moves bytes around.
+        let same_layout = old.is_struct == new.is_struct
+            && old.bytesize == new.bytesize
+            && old.fields.iter().all(|old_field| {
+                new.fields.iter().any(|new_field| {
+                    new_field.offset == old_field.offset && same_type(old_field, new_field)
+                })
+            });
+        if !same_layout {
+            changes.push(BreakingChange::StructChanged { name: name.clone() });
+        }
+        return;
+    }
+    for old_field in &old.fields {
+        let new_field = match new.fields.iter().find(|f| f.name == old_field.name) {
+            Some(new_field) if new_field.id != old_field.id => {
+                changes.push(BreakingChange::FieldIdChanged {
+                    table: name.clone(),
+                    field: old_field.name.clone(),
+                    old: old_field.id,
+                    new: new_field.id,
+                });
+                continue;
+            }
+            Some(new_field) => new_field,
+            // A renamed field keeps its id.
+            None => match new.fields.iter().find(|f| f.id == old_field.id) {
+                Some(new_field) => new_field,
+                None => {
+                    changes.push(BreakingChange::FieldRemoved {
+                        table: name.clone(),
+                        field: old_field.name.clone(),
+                    });
+                    continue;
+                }
+            },
+        };
+        if !same_type(old_field, new_field) {
+            changes.push(BreakingChange::FieldTypeChanged {
+                table: name.clone(),
+                field: old_field.name.clone(),
+                old: old_field.type_name.clone(),
+                new: new_field.type_name.clone(),
+            });
+        }
+        if old_field.required != new_field.required {
+            changes.push(BreakingChange::FieldRequiredChanged {
+                table: name.clone(),
+                field: old_field.name.clone(),
+                required: new_field.required,
+            });
+        }
+    }
+}
+
+fn check_enum(old: &EnumSnapshot, new: &EnumSnapshot, changes: &mut Vec<BreakingChange>) {
+    let name = &old.name;
+    if old.underlying != new.underlying {
+        changes.push(BreakingChange::EnumTypeChanged {
+            name: name.clone(),
+            old: old.underlying.clone(),
+            new: new.underlying.clone(),
+        });
+    }
+    for old_value in &old.values {
+        let Some(new_value) = new.values.iter().find(|v| v.name == old_value.name) else {
+            changes.push(BreakingChange::EnumValueRemoved {
+                name: name.clone(),
+                value: old_value.name.clone(),
+            });
+            continue;
+        };
+        if new_value.value != old_value.value {
+            changes.push(BreakingChange::EnumValueChanged {
+                name: name.clone(),
+                value: old_value.name.clone(),
+                old: old_value.value,
+                new: new_value.value,
+            });
+        }
+        // Snapshots of older versions have no union types.
+        match (&old_value.union_type, &new_value.union_type) {
+            (Some(old_type), Some(new_type)) if old_type != new_type => {
+                changes.push(BreakingChange::UnionVariantChanged {
+                    name: name.clone(),
+                    value: old_value.name.clone(),
+                    old: old_type.clone(),
+                    new: new_type.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Readable type of a field, i.e. `int`, `[string]` or `sample.Monster`.
+/// Tables, structs and enums are named since their indexes differ between schemas,
+/// one out of range is its base type and index, i.e. `obj#7`, without spaces to keep
+/// the snapshot line.
+fn type_name(schema: &Schema, ty: &Type) -> String {
+    match ty.base_type() {
+        BaseType::Vector => format!("[{}]", element_name(schema, ty.element(), ty.index())),
+        BaseType::Array => format!(
+            "[{}:{}]",
+            element_name(schema, ty.element(), ty.index()),
+            ty.fixed_length()
+        ),
+        base => element_name(schema, base, ty.index()),
+    }
+}
+
+fn element_name(schema: &Schema, base: BaseType, index: i32) -> String {
+    if index < 0 {
+        return base_type_name(base);
+    }
+    let name = match base {
+        BaseType::Obj => object_at(schema, index).map(|object| object.name()),
+        _ => enum_at(schema, index).map(|e| e.name()),
+    };
+    name.map(str::to_string)
+        .unwrap_or_else(|_| format!("{}#{index}", base_type_name(base)))
+}
+
+fn base_type_name(base: BaseType) -> String {
+    base.variant_name()
+        .map(|name| name.to_lowercase())
+        .unwrap_or_else(|| format!("{base:?}"))
+}
@@ -0,0 +1,603 @@
+use std::collections::BTreeMap;
+
+use flatbuffers::{
+    FlatBufferBuilder, Follow, InvalidFlatbuffer, Push, UnionWIPOffset, VOffsetT, Verifiable,
+    WIPOffset,
+};
+use flatbuffers_reflection::reflection::{BaseType, Field, Object, Schema, Type};
+
+use super::table::{DynamicTable, DynamicValue};
+use super::verify::{enum_at, find_field, find_object, object_at, unsupported};
+use crate::OwnedFB;
+
+/// Errors from the schema driven (dynamic) access to flatbuffers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicError {
+    /// The type is not declared in the schema.
+    TypeNotFound(String),
+    /// The field is not declared in the table.
+    FieldNotFound(String),
+    /// The value does not match the type declared in the schema.
+    TypeMismatch(String),
+    /// The schema uses a feature not supported yet, i.e. vector of unions.
+    Unsupported(String),
+    /// The buffer does not match the schema.
+    InvalidFlatbuffer(InvalidFlatbuffer),
+}
+
+impl std::fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DynamicError::TypeNotFound(name) => write!(f, "type not found: {name}"),
+            DynamicError::FieldNotFound(name) => write!(f, "field not found: {name}"),
+            DynamicError::TypeMismatch(msg) => write!(f, "type mismatch: {msg}"),
+            DynamicError::Unsupported(msg) => write!(f, "not supported: {msg}"),
+            DynamicError::InvalidFlatbuffer(e) => write!(f, "invalid flatbuffer: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DynamicError {}
+
+impl From<InvalidFlatbuffer> for DynamicError {
+    fn from(value: InvalidFlatbuffer) -> Self {
+        DynamicError::InvalidFlatbuffer(value)
+    }
+}
+
+/// Value of a field in a DynamicMessage.
+/// Enums are stored as their underlying integer type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Bool(bool),
+    Byte(i8),
+    UByte(u8),
+    Short(i16),
+    UShort(u16),
+    Int(i32),
+    UInt(u32),
+    Long(i64),
+    ULong(u64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    /// Struct in its flatbuffers (little endian, padded) layout.
+    Struct(Vec<u8>),
+    Table(DynamicMessage<'a>),
+    /// Union holding a table. The union type field is derived from it.
+    Union(Box<DynamicMessage<'a>>),
+    Vector(Vec<Value<'a>>),
+}
+
+impl<'a> Value<'a> {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Any integer (including bool and enums) widened to i64.
+    /// u64 values that do not fit return None.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Bool(v) => Some(v as i64),
+            Value::Byte(v) => Some(v as i64),
+            Value::UByte(v) => Some(v as i64),
+            Value::Short(v) => Some(v as i64),
+            Value::UShort(v) => Some(v as i64),
+            Value::Int(v) => Some(v as i64),
+            Value::UInt(v) => Some(v as i64),
+            Value::Long(v) => Some(v),
+            Value::ULong(v) => i64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Float(v) => Some(v as f64),
+            Value::Double(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_message(&self) -> Option<&DynamicMessage<'a>> {
+        match self {
+            Value::Table(m) => Some(m),
+            Value::Union(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    pub fn as_message_mut(&mut self) -> Option<&mut DynamicMessage<'a>> {
+        match self {
+            Value::Table(m) => Some(m),
+            Value::Union(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    pub fn as_vector(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Value::Vector(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Default value of a scalar field. None for non scalar fields.
+    pub fn default_for(field: &Field) -> Option<Value<'a>> {
+        let i = field.default_integer();
+        let r = field.default_real();
+        let value = match field.type_().base_type() {
+            BaseType::Bool => Value::Bool(i != 0),
+            BaseType::Byte => Value::Byte(i as i8),
+            BaseType::UByte => Value::UByte(i as u8),
+            BaseType::Short => Value::Short(i as i16),
+            BaseType::UShort => Value::UShort(i as u16),
+            BaseType::Int => Value::Int(i as i32),
+            BaseType::UInt => Value::UInt(i as u32),
+            BaseType::Long => Value::Long(i),
+            BaseType::ULong => Value::ULong(i as u64),
+            BaseType::Float => Value::Float(r as f32),
+            BaseType::Double => Value::Double(r),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// A message of any table type in the schema, decoded into owned values so
+/// fields can be read, set and cleared by name and then re-serialized.
+/// Similar to prost-reflect's DynamicMessage.
+#[derive(Clone)]
+pub struct DynamicMessage<'a> {
+    schema: Schema<'a>,
+    object: Object<'a>,
+    /// Present fields by field id. Union type fields are never stored.
+    fields: BTreeMap<u16, Value<'a>>,
+}
+
+impl std::fmt::Debug for DynamicMessage<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut map = f.debug_map();
+        for (field, value) in self.fields() {
+            map.entry(&field.name(), value);
+        }
+        map.finish()
+    }
+}
+
+impl PartialEq for DynamicMessage<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.object.name() == other.object.name() && self.fields == other.fields
+    }
+}
+
+impl<'a> DynamicMessage<'a> {
+    /// Empty message of the table type, i.e. `fbs.helloworld.HelloRequest`.
+    pub fn new(schema: &Schema<'a>, type_name: &str) -> Result<Self, DynamicError> {
+        let object = find_object(schema, type_name)?;
+        Self::new_from_object(schema, object)
+    }
+
+    pub fn new_from_object(schema: &Schema<'a>, object: Object<'a>) -> Result<Self, DynamicError> {
+        if object.is_struct() {
+            return Err(DynamicError::TypeMismatch(format!(
+                "{} is a struct, not a table",
+                object.name()
+            )));
+        }
+        Ok(Self {
+            schema: *schema,
+            object,
+            fields: BTreeMap::new(),
+        })
+    }
+
+    /// Verify the buffer against the schema and decode the root table.
+    /// `buf` starts with the root offset, i.e. `OwnedFB::get_slice()`.
+    pub fn decode(schema: &Schema<'a>, type_name: &str, buf: &[u8]) -> Result<Self, DynamicError> {
+        Self::from_table_in(schema, &DynamicTable::new(buf, schema, type_name)?)
+    }
+
+    /// Copy the fields of the table view.
+    pub fn from_table(table: &DynamicTable<'a>) -> Result<Self, DynamicError> {
+        Self::from_table_in(&table.schema(), table)
+    }
+
+    /// Copy the fields of the table view, with types resolved in `schema`.
+    pub(crate) fn from_table_in(
+        schema: &Schema<'a>,
+        table: &DynamicTable,
+    ) -> Result<Self, DynamicError> {
+        let mut msg = Self::new(schema, table.type_name())?;
+        for (field, value) in table.fields() {
+            if field.type_().base_type() == BaseType::Union
+                && !matches!(value, DynamicValue::Union(_))
+            {
+                return Err(unsupported(&field, BaseType::Union));
+            }
+            msg.fields.insert(field.id(), value.to_value_in(schema)?);
+        }
+        Ok(msg)
+    }
+
+    /// The table descriptor from the schema.
+    pub fn descriptor(&self) -> Object<'a> {
+        self.object
+    }
+
+    /// Fully qualified type name.
+    pub fn type_name(&self) -> &'a str {
+        self.object.name()
+    }
+
+    /// Find the field descriptor by name.
+    pub fn field(&self, name: &str) -> Result<Field<'a>, DynamicError> {
+        find_field(&self.object, name)
+    }
+
+    fn field_by_id(&self, id: u16) -> Field<'a> {
+        self.object
+            .fields()
+            .iter()
+            .find(|f| f.id() == id)
+            .expect("field id from the same object")
+    }
+
+    /// Present fields with their descriptors, in field id order.
+    pub fn fields(&self) -> impl Iterator<Item = (Field<'a>, &Value<'a>)> {
+        self.fields
+            .iter()
+            .map(|(id, value)| (self.field_by_id(*id), value))
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.field(name)
+            .is_ok_and(|f| self.fields.contains_key(&f.id()))
+    }
+
+    /// Value of the field if it is present.
+    pub fn get(&self, name: &str) -> Option<&Value<'a>> {
+        let field = self.field(name).ok()?;
+        self.fields.get(&field.id())
+    }
+
+    /// Value of the field, or the schema default for absent scalar fields.
+    pub fn get_or_default(&self, name: &str) -> Option<Value<'a>> {
+        let field = self.field(name).ok()?;
+        match self.fields.get(&field.id()) {
+            Some(v) => Some(v.clone()),
+            None => Value::default_for(&field),
+        }
+    }
+
+    /// Mutable value of the field if it is present. The value is checked against
+    /// the schema when the message is encoded.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Value<'a>> {
+        let field = self.field(name).ok()?;
+        self.fields.get_mut(&field.id())
+    }
+
+    /// Set the field, the value must match the type declared in the schema.
+    pub fn set(&mut self, name: &str, value: Value<'a>) -> Result<(), DynamicError> {
+        let field = self.field(name)?;
+        if field.type_().base_type() == BaseType::UType {
+            return Err(DynamicError::TypeMismatch(format!(
+                "{name} is derived from the union value"
+            )));
+        }
+        check_value(&self.schema, &field, &field.type_(), &value, false)?;
+        self.fields.insert(field.id(), value);
+        Ok(())
+    }
+
+    /// Remove the field, so it is absent in the encoded message.
+    pub fn clear(&mut self, name: &str) -> Result<Option<Value<'a>>, DynamicError> {
+        let field = self.field(name)?;
+        Ok(self.fields.remove(&field.id()))
+    }
+
+    /// Merge the present fields of `other` into this message.
+    /// Nested tables of the same type are merged recursively,
+    /// all other fields (including vectors) are replaced.
+    pub fn merge(&mut self, other: &DynamicMessage<'a>) -> Result<(), DynamicError> {
+        if self.type_name() != other.type_name() {
+            return Err(DynamicError::TypeMismatch(format!(
+                "cannot merge {} into {}",
+                other.type_name(),
+                self.type_name()
+            )));
+        }
+        for (id, value) in &other.fields {
+            match (self.fields.get_mut(id), value) {
+                (Some(Value::Table(this)), Value::Table(that)) => this.merge(that)?,
+                (Some(Value::Union(this)), Value::Union(that))
+                    if this.type_name() == that.type_name() =>
+                {
+                    this.merge(that)?
+                }
+                _ => {
+                    self.fields.insert(*id, value.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize into flatbuffers bytes, the returned vec is the finished data.
+    pub fn encode_to_vec(&self) -> Result<Vec<u8>, DynamicError> {
+        let (buf, index) = self.encode_collapse()?;
+        Ok(buf[index..].to_vec())
+    }
+
+    /// Serialize into a typed OwnedFB, which is verified against the generated type.
+    pub fn encode<T>(&self) -> Result<OwnedFB<T>, DynamicError>
+    where
+        T: Verifiable + Follow<'static> + 'static,
+    {
+        let (buf, index) = self.encode_collapse()?;
+        Ok(OwnedFB::new_from_vec(buf, index)?)
+    }
+
+    fn encode_collapse(&self) -> Result<(Vec<u8>, usize), DynamicError> {
+        let mut builder = FlatBufferBuilder::new();
+        let root = self.write_table(&mut builder)?;
+        builder.finish_minimal(root);
+        Ok(builder.collapse())
+    }
+
+    fn write_table<'fbb>(
+        &self,
+        builder: &mut FlatBufferBuilder<'fbb>,
+    ) -> Result<WIPOffset<UnionWIPOffset>, DynamicError> {
+        // Offsets need to be created before the table is started.
+        let mut offsets = Vec::new();
+        for (field, value) in self.fields() {
+            // Values changed through `get_mut` are not checked by `set`.
+            check_value(&self.schema, &field, &field.type_(), value, false)?;
+            let offset = match value {
+                Value::String(s) => builder.create_string(s).as_union_value(),
+                Value::Table(m) => m.write_table(builder)?,
+                Value::Union(m) => m.write_table(builder)?,
+                Value::Vector(v) => write_vector(&self.schema, &field, v, builder)?,
+                _ => continue,
+            };
+            offsets.push((field.offset(), offset));
+        }
+
+        let start = builder.start_table();
+        for (field, value) in self.fields() {
+            let slot = field.offset();
+            match value {
+                Value::Struct(bytes) => {
+                    let object = object_at(&self.schema, field.type_().index())?;
+                    push_struct(builder, &object, bytes, Some(slot))?;
+                }
+                Value::Union(m) => {
+                    builder.push_slot_always::<u8>(slot - 2, union_tag(&self.schema, &field, m)?);
+                }
+                Value::String(_) | Value::Table(_) | Value::Vector(_) => {}
+                scalar => push_scalar_slot(builder, slot, scalar),
+            }
+        }
+        for (slot, offset) in offsets {
+            builder.push_slot_always(slot, offset);
+        }
+        let end = builder.end_table(start);
+        Ok(WIPOffset::new(end.value()))
+    }
+}
+
+fn write_vector<'fbb>(
+    schema: &Schema,
+    field: &Field,
+    values: &[Value],
+    builder: &mut FlatBufferBuilder<'fbb>,
+) -> Result<WIPOffset<UnionWIPOffset>, DynamicError> {
+    macro_rules! scalars {
+        ($variant:ident) => {{
+            let v = values
+                .iter()
+                .map(|v| match v {
+                    Value::$variant(x) => *x,
+                    _ => unreachable!("checked before writing"),
+                })
+                .collect::<Vec<_>>();
+            builder.create_vector(&v).as_union_value()
+        }};
+    }
+    let offset = match field.type_().element() {
+        BaseType::Bool => scalars!(Bool),
+        BaseType::Byte => scalars!(Byte),
+        BaseType::UType | BaseType::UByte => scalars!(UByte),
+        BaseType::Short => scalars!(Short),
+        BaseType::UShort => scalars!(UShort),
+        BaseType::Int => scalars!(Int),
+        BaseType::UInt => scalars!(UInt),
+        BaseType::Long => scalars!(Long),
+        BaseType::ULong => scalars!(ULong),
+        BaseType::Float => scalars!(Float),
+        BaseType::Double => scalars!(Double),
+        BaseType::String => {
+            let strings = values
+                .iter()
+                .map(|v| builder.create_string(v.as_str().expect("checked before writing")))
+                .collect::<Vec<_>>();
+            builder.create_vector(&strings).as_union_value()
+        }
+        BaseType::Obj => {
+            let object = object_at(schema, field.type_().index())?;
+            if object.is_struct() {
+                let size = object.bytesize() as usize;
+                let mut bytes = Vec::with_capacity(values.len() * size);
+                for v in values {
+                    match v {
+                        Value::Struct(b) => bytes.extend_from_slice(b),
+                        _ => unreachable!("checked before writing"),
+                    }
+                }
+                // Struct vectors are written as aligned chunks, the length is the element count.
+                let align = object.minalign() as usize;
+                match align {
+                    1 => builder.start_vector::<u8>(bytes.len()),
+                    2 => builder.start_vector::<u16>(bytes.len() / 2),
+                    4 => builder.start_vector::<u32>(bytes.len() / 4),
+                    _ => builder.start_vector::<u64>(bytes.len() / 8),
+                }
+                push_struct(builder, &object, &bytes, None)?;
+                builder.end_vector::<u8>(values.len()).as_union_value()
+            } else {
+                let mut tables = Vec::with_capacity(values.len());
+                for v in values {
+                    let m = v.as_message().expect("checked before writing");
+                    tables.push(m.write_table(builder)?);
+                }
+                builder.create_vector(&tables).as_union_value()
+            }
+        }
+        other => return Err(unsupported(field, other)),
+    };
+    Ok(offset)
+}
+
+fn push_scalar_slot(builder: &mut FlatBufferBuilder, slot: VOffsetT, value: &Value) {
+    match *value {
+        Value::Bool(v) => builder.push_slot_always(slot, v),
+        Value::Byte(v) => builder.push_slot_always(slot, v),
+        Value::UByte(v) => builder.push_slot_always(slot, v),
+        Value::Short(v) => builder.push_slot_always(slot, v),
+        Value::UShort(v) => builder.push_slot_always(slot, v),
+        Value::Int(v) => builder.push_slot_always(slot, v),
+        Value::UInt(v) => builder.push_slot_always(slot, v),
+        Value::Long(v) => builder.push_slot_always(slot, v),
+        Value::ULong(v) => builder.push_slot_always(slot, v),
+        Value::Float(v) => builder.push_slot_always(slot, v),
+        Value::Double(v) => builder.push_slot_always(slot, v),
+        _ => unreachable!("not a scalar"),
+    }
+}
+
+/// Push the struct bytes (one or many structs) back to front in chunks of the
+/// struct alignment, so the builder keeps the struct layout and alignment.
+/// With a slot, the struct is tracked as the table field.
+fn push_struct(
+    builder: &mut FlatBufferBuilder,
+    object: &Object,
+    bytes: &[u8],
+    slot: Option<VOffsetT>,
+) -> Result<(), DynamicError> {
+    fn push_chunks<C: Push + Copy>(
+        builder: &mut FlatBufferBuilder,
+        bytes: &[u8],
+        slot: Option<VOffsetT>,
+        from_le: fn(&[u8]) -> C,
+    ) {
+        let mut chunks = bytes
+            .chunks_exact(std::mem::size_of::<C>())
+            .rev()
+            .peekable();
+        while let Some(chunk) = chunks.next() {
+            let chunk = from_le(chunk);
+            match slot {
+                Some(slot) if chunks.peek().is_none() => builder.push_slot_always(slot, chunk),
+                _ => {
+                    builder.push(chunk);
+                }
+            }
+        }
+    }
+    match object.minalign() {
+        1 => push_chunks::<u8>(builder, bytes, slot, |c| c[0]),
+        2 => push_chunks(builder, bytes, slot, |c| u16::from_le_bytes([c[0], c[1]])),
+        4 => push_chunks(builder, bytes, slot, |c| {
+            u32::from_le_bytes(c.try_into().unwrap())
+        }),
+        8 => push_chunks(builder, bytes, slot, |c| {
+            u64::from_le_bytes(c.try_into().unwrap())
+        }),
+        align => {
+            return Err(DynamicError::Unsupported(format!(
+                "{} with alignment {align}",
+                object.name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn union_tag(schema: &Schema, field: &Field, msg: &DynamicMessage) -> Result<u8, DynamicError> {
+    let union_enum = enum_at(schema, field.type_().index())?;
+    union_enum
+        .values()
+        .iter()
+        .find(|v| {
+            v.union_type().is_some_and(|t| {
+                t.base_type() == BaseType::Obj
+                    && object_at(schema, t.index()).is_ok_and(|o| o.name() == msg.type_name())
+            })
+        })
+        .map(|v| v.value() as u8)
+        .ok_or_else(|| {
+            DynamicError::TypeMismatch(format!(
+                "{} is not a variant of {}",
+                msg.type_name(),
+                union_enum.name()
+            ))
+        })
+}
+
+/// Check the value against the field type. `element` checks a vector element.
+fn check_value(
+    schema: &Schema,
+    field: &Field,
+    field_type: &Type,
+    value: &Value,
+    element: bool,
+) -> Result<(), DynamicError> {
+    let base_type = if element {
+        field_type.element()
+    } else {
+        field_type.base_type()
+    };
+    let ok = match (base_type, value) {
+        (BaseType::Bool, Value::Bool(_))
+        | (BaseType::Byte, Value::Byte(_))
+        | (BaseType::UType | BaseType::UByte, Value::UByte(_))
+        | (BaseType::Short, Value::Short(_))
+        | (BaseType::UShort, Value::UShort(_))
+        | (BaseType::Int, Value::Int(_))
+        | (BaseType::UInt, Value::UInt(_))
+        | (BaseType::Long, Value::Long(_))
+        | (BaseType::ULong, Value::ULong(_))
+        | (BaseType::Float, Value::Float(_))
+        | (BaseType::Double, Value::Double(_))
+        | (BaseType::String, Value::String(_)) => true,
+        (BaseType::Obj, Value::Struct(bytes)) => {
+            let object = object_at(schema, field_type.index())?;
+            object.is_struct() && bytes.len() == object.bytesize() as usize
+        }
+        (BaseType::Obj, Value::Table(m)) => {
+            let object = object_at(schema, field_type.index())?;
+            !object.is_struct() && object.name() == m.type_name()
+        }
+        (BaseType::Union, Value::Union(m)) => union_tag(schema, field, m).is_ok(),
+        (BaseType::Vector, Value::Vector(values)) if !element => {
+            for v in values {
+                check_value(schema, field, field_type, v, true)?;
+            }
+            true
+        }
+        _ => false,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(DynamicError::TypeMismatch(format!(
+            "{} of type {} cannot hold {value:?}",
+            field.name(),
+            base_type.variant_name().unwrap_or_default()
+        )))
+    }
+}
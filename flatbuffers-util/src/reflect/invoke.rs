@@ -25,6 +25,14 @@ fn get_flatbuffers_util_temp_dir() -> PathBuf {
 /// to store the generated bfbs file, then read it back into an OwnedFB<Schema>
 pub fn compile_reflection_schema(
     fbs_path: &Path,
+) -> crate::ownedfb::OwnedFB<flatbuffers_reflection::reflection::Schema<'static>> {
+    compile_reflection_schema_with_includes::<&Path>(fbs_path, &[])
+}
+
+/// Like [`compile_reflection_schema`], searching `include_paths` for included fbs files.
+pub fn compile_reflection_schema_with_includes<P: AsRef<Path>>(
+    fbs_path: &Path,
+    include_paths: &[P],
 ) -> crate::ownedfb::OwnedFB<flatbuffers_reflection::reflection::Schema<'static>> {
     let flatc_path = ensure_flatc();
     // out file has a different extension
@@ -38,10 +46,22 @@ pub fn compile_reflection_schema(
     let temp_full_dir = get_flatbuffers_util_temp_dir().join(temp_subdir);
     fs::create_dir_all(&temp_full_dir).expect("Failed to create temp dir");
 
+    let fbs_dir = match fbs_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let includes = include_paths
+        .iter()
+        .flat_map(|dir| ["-I".as_ref(), dir.as_ref().as_os_str()]);
     let status = std::process::Command::new(flatc_path)
+        .args(includes)
         .args([
             "--binary",
             "--schema",
+            "--bfbs-comments",
+            // Declaration files of types are relative to the compiled file.
+            "--bfbs-filenames",
+            fbs_dir.to_str().unwrap(),
             "-o",
             temp_full_dir.to_str().unwrap(),
             fbs_path.to_str().unwrap(),
@@ -1,7 +1,23 @@
 pub use flatbuffers_reflection as reflection;
 
 mod invoke;
-pub use invoke::compile_reflection_schema;
+pub use invoke::{compile_reflection_schema, compile_reflection_schema_with_includes};
+
+pub mod compat;
 
 mod code_gen;
-pub use code_gen::{GeneratorContext, MessageType, Method, Service, collect_in_out_types};
+pub use code_gen::{
+    GeneratorContext, MessageType, Method, Service, collect_in_out_types, namespace_rs,
+};
+
+mod model;
+pub use model::{EnumDef, EnumValueDef, FieldDef, FieldType, ObjectDef};
+
+mod verify;
+pub use verify::{find_object, verify_with_schema};
+
+mod dynamic;
+pub use dynamic::{DynamicError, DynamicMessage, Value};
+
+mod table;
+pub use table::{DynamicStruct, DynamicTable, DynamicValue, DynamicVector};
@@ -0,0 +1,265 @@
+use std::{collections::BTreeMap, fmt};
+
+use flatbuffers_reflection::reflection::{BaseType, Enum, Field, Object, Schema, Type};
+
+use super::{
+    code_gen::{attributes_map, documentation_lines},
+    dynamic::DynamicError,
+    verify::{enum_at, object_at},
+};
+
+/// Type of a field, tables, structs, enums and unions are referenced by fully qualified name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    /// `bool`, `int`, `double` and the other scalars.
+    Scalar(BaseType),
+    String,
+    Table(String),
+    Struct(String),
+    Enum {
+        name: String,
+        underlying: BaseType,
+    },
+    Union(String),
+    /// The hidden `<field>_type` discriminant of a union field.
+    UnionType(String),
+    Vector(Box<FieldType>),
+    /// Fixed length array, only in structs.
+    Array(Box<FieldType>, u16),
+}
+
+impl FieldType {
+    fn from_type(schema: &Schema, ty: &Type) -> Result<Self, DynamicError> {
+        Ok(match ty.base_type() {
+            BaseType::Vector => FieldType::Vector(Box::new(Self::element(schema, ty)?)),
+            BaseType::Array => {
+                FieldType::Array(Box::new(Self::element(schema, ty)?), ty.fixed_length())
+            }
+            base => Self::named(schema, base, ty.index())?,
+        })
+    }
+
+    fn element(schema: &Schema, ty: &Type) -> Result<Self, DynamicError> {
+        Self::named(schema, ty.element(), ty.index())
+    }
+
+    fn named(schema: &Schema, base: BaseType, index: i32) -> Result<Self, DynamicError> {
+        if index < 0 {
+            return Ok(match base {
+                BaseType::String => FieldType::String,
+                base => FieldType::Scalar(base),
+            });
+        }
+        Ok(match base {
+            BaseType::Obj => {
+                let object = object_at(schema, index)?;
+                let name = object.name().to_string();
+                if object.is_struct() {
+                    FieldType::Struct(name)
+                } else {
+                    FieldType::Table(name)
+                }
+            }
+            BaseType::Union => FieldType::Union(enum_at(schema, index)?.name().to_string()),
+            BaseType::UType => FieldType::UnionType(enum_at(schema, index)?.name().to_string()),
+            underlying => FieldType::Enum {
+                name: enum_at(schema, index)?.name().to_string(),
+                underlying,
+            },
+        })
+    }
+}
+
+/// Schema language spelling, i.e. `int`, `[string]`, `[ubyte:4]` or `sample.Monster`.
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Scalar(base) => match base.variant_name() {
+                Some(name) => write!(f, "{}", name.to_lowercase()),
+                None => write!(f, "{base:?}"),
+            },
+            FieldType::String => write!(f, "string"),
+            FieldType::Table(name)
+            | FieldType::Struct(name)
+            | FieldType::Enum { name, .. }
+            | FieldType::Union(name)
+            | FieldType::UnionType(name) => write!(f, "{name}"),
+            FieldType::Vector(element) => write!(f, "[{element}]"),
+            FieldType::Array(element, len) => write!(f, "[{element}:{len}]"),
+        }
+    }
+}
+
+/// Field of a table or struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDef {
+    pub name: String,
+    /// Position in the vtable, the wire identity of the field.
+    pub id: u16,
+    pub field_type: FieldType,
+    /// Default of integer, bool and enum fields.
+    pub default_integer: i64,
+    /// Default of float and double fields.
+    pub default_real: f64,
+    pub required: bool,
+    pub deprecated: bool,
+    /// Scalar declared with `= null`, absent rather than defaulted.
+    pub optional: bool,
+    /// Byte offset in a struct, vtable offset in a table.
+    pub offset: u16,
+    pub attributes: BTreeMap<String, String>,
+    pub documentation: Vec<String>,
+}
+
+impl FieldDef {
+    fn new(schema: &Schema, field: &Field) -> Result<Self, DynamicError> {
+        Ok(FieldDef {
+            name: field.name().to_string(),
+            id: field.id(),
+            field_type: FieldType::from_type(schema, &field.type_())?,
+            default_integer: field.default_integer(),
+            default_real: field.default_real(),
+            required: field.required(),
+            deprecated: field.deprecated(),
+            optional: field.optional(),
+            offset: field.offset(),
+            attributes: attributes_map(field.attributes()),
+            documentation: documentation_lines(field.documentation()),
+        })
+    }
+}
+
+/// A table or struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectDef {
+    /// Fully qualified name, i.e. `sample.sample_request`.
+    pub name: String,
+    pub is_struct: bool,
+    /// Size of a struct, 0 for tables.
+    pub bytesize: i32,
+    pub minalign: i32,
+    /// Fields in id order.
+    pub fields: Vec<FieldDef>,
+    pub attributes: BTreeMap<String, String>,
+    pub documentation: Vec<String>,
+    /// The declaring fbs file relative to the compiled one, i.e. `greeter.fbs`.
+    pub declaration_file: Option<String>,
+}
+
+impl ObjectDef {
+    pub(crate) fn new(schema: &Schema, object: &Object) -> Result<Self, DynamicError> {
+        let mut fields = object
+            .fields()
+            .iter()
+            .map(|field| FieldDef::new(schema, &field))
+            .collect::<Result<Vec<_>, _>>()?;
+        // The schema sorts fields by name.
+        fields.sort_by_key(|field| field.id);
+        Ok(ObjectDef {
+            name: object.name().to_string(),
+            is_struct: object.is_struct(),
+            bytesize: object.bytesize(),
+            minalign: object.minalign(),
+            fields,
+            attributes: attributes_map(object.attributes()),
+            documentation: documentation_lines(object.documentation()),
+            declaration_file: declaration_file(object.declaration_file()),
+        })
+    }
+
+    /// The name without namespace.
+    pub fn short_name(&self) -> &str {
+        split_name(&self.name).1
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        split_name(&self.name).0
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldDef> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// Value of an enum, or variant of a union.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValueDef {
+    pub name: String,
+    pub value: i64,
+    /// The table of a union variant, `None` for `NONE` and enum values.
+    pub union_type: Option<String>,
+    pub attributes: BTreeMap<String, String>,
+    pub documentation: Vec<String>,
+}
+
+/// An enum or union.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDef {
+    /// Fully qualified name, i.e. `sample.Color`.
+    pub name: String,
+    pub is_union: bool,
+    /// Integer type of the values, `utype` for unions.
+    pub underlying: BaseType,
+    /// Values in declaration order.
+    pub values: Vec<EnumValueDef>,
+    pub attributes: BTreeMap<String, String>,
+    pub documentation: Vec<String>,
+    /// The declaring fbs file relative to the compiled one, i.e. `greeter.fbs`.
+    pub declaration_file: Option<String>,
+}
+
+impl EnumDef {
+    pub(crate) fn new(schema: &Schema, e: &Enum) -> Result<Self, DynamicError> {
+        let values = e
+            .values()
+            .iter()
+            .map(|value| {
+                let union_type = value
+                    .union_type()
+                    .filter(|ty| ty.base_type() == BaseType::Obj)
+                    .map(|ty| object_at(schema, ty.index()))
+                    .transpose()?;
+                Ok(EnumValueDef {
+                    name: value.name().to_string(),
+                    value: value.value(),
+                    union_type: union_type.map(|object| object.name().to_string()),
+                    attributes: attributes_map(value.attributes()),
+                    documentation: documentation_lines(value.documentation()),
+                })
+            })
+            .collect::<Result<_, DynamicError>>()?;
+        Ok(EnumDef {
+            name: e.name().to_string(),
+            is_union: e.is_union(),
+            underlying: e.underlying_type().base_type(),
+            values,
+            attributes: attributes_map(e.attributes()),
+            documentation: documentation_lines(e.documentation()),
+            declaration_file: declaration_file(e.declaration_file()),
+        })
+    }
+
+    /// The name without namespace.
+    pub fn short_name(&self) -> &str {
+        split_name(&self.name).1
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        split_name(&self.name).0
+    }
+}
+
+/// Split `a.b.Name` into the namespace and the short name.
+pub(crate) fn split_name(name: &str) -> (Option<&str>, &str) {
+    match name.rsplit_once('.') {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, name),
+    }
+}
+
+/// flatc writes declaration files as `//<path>`, empty without `--bfbs-filenames`.
+fn declaration_file(file: Option<&str>) -> Option<String> {
+    file.map(|f| f.trim_start_matches("//"))
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,512 @@
+use flatbuffers::{Follow, ForwardsUOffset, SIZE_UOFFSET, Table, read_scalar_at};
+use flatbuffers_reflection::reflection::{BaseType, Field, Object, Schema, Type};
+
+use super::verify::{find_field, find_object, object_at, union_variant, verify_with_schema};
+use super::{DynamicError, DynamicMessage, Value};
+
+/// Verified zero copy view of a table of any type in the schema.
+/// The buffer is verified with the schema once on creation, after which
+/// fields can be read by name and the structure walked without copies.
+#[derive(Clone, Copy)]
+pub struct DynamicTable<'a> {
+    schema: Schema<'a>,
+    object: Object<'a>,
+    table: Table<'a>,
+}
+
+/// Field value in a DynamicTable.
+/// Enums are their underlying integer type.
+#[derive(Debug, Clone, Copy)]
+pub enum DynamicValue<'a> {
+    Bool(bool),
+    Byte(i8),
+    UByte(u8),
+    Short(i16),
+    UShort(u16),
+    Int(i32),
+    UInt(u32),
+    Long(i64),
+    ULong(u64),
+    Float(f32),
+    Double(f64),
+    String(&'a str),
+    Struct(DynamicStruct<'a>),
+    Table(DynamicTable<'a>),
+    /// Union holding a table. Struct and string variants are Struct and String.
+    Union(DynamicTable<'a>),
+    /// Vector field, or fixed size array in a struct.
+    Vector(DynamicVector<'a>),
+}
+
+/// View of a struct, fields are always present.
+#[derive(Clone, Copy)]
+pub struct DynamicStruct<'a> {
+    schema: Schema<'a>,
+    object: Object<'a>,
+    bytes: &'a [u8],
+}
+
+/// View of a vector field, or fixed size array in a struct.
+#[derive(Clone, Copy)]
+pub struct DynamicVector<'a> {
+    schema: Schema<'a>,
+    element: BaseType,
+    /// Type index of struct and table elements.
+    index: i32,
+    buf: &'a [u8],
+    start: usize,
+    len: usize,
+    element_size: usize,
+}
+
+impl<'a> DynamicTable<'a> {
+    /// Verify the buffer against the schema and view the root table of `type_name`.
+    /// `buf` starts with the root offset, i.e. `OwnedFB::get_slice()`.
+    pub fn new(buf: &'a [u8], schema: &Schema<'a>, type_name: &str) -> Result<Self, DynamicError> {
+        let object = find_object(schema, type_name)?;
+        verify_with_schema(buf, 0, schema, &object)?;
+        // Safety: The buffer is verified above.
+        let table = unsafe { <ForwardsUOffset<Table>>::follow(buf, 0) };
+        Ok(Self {
+            schema: *schema,
+            object,
+            table,
+        })
+    }
+
+    pub fn schema(&self) -> Schema<'a> {
+        self.schema
+    }
+
+    /// The table descriptor from the schema.
+    pub fn descriptor(&self) -> Object<'a> {
+        self.object
+    }
+
+    /// Fully qualified type name.
+    pub fn type_name(&self) -> &'a str {
+        self.object.name()
+    }
+
+    /// Find the field descriptor by name.
+    pub fn field(&self, name: &str) -> Result<Field<'a>, DynamicError> {
+        find_field(&self.object, name)
+    }
+
+    /// Value of the field, None if it is absent in the buffer.
+    pub fn get(&self, name: &str) -> Result<Option<DynamicValue<'a>>, DynamicError> {
+        let field = self.field(name)?;
+        Ok(self.read_field(&field))
+    }
+
+    /// Present fields in field id order. Union type fields are skipped,
+    /// the variant is the type of the union value.
+    pub fn fields(&self) -> impl Iterator<Item = (Field<'a>, DynamicValue<'a>)> + 'a {
+        let this = *self;
+        let mut fields = self.object.fields().iter().collect::<Vec<_>>();
+        fields.sort_by_key(|f| f.id());
+        fields
+            .into_iter()
+            .filter_map(move |f| this.read_field(&f).map(|v| (f, v)))
+    }
+
+    /// Visit all present fields depth first, with the path from this table,
+    /// i.e. `weapons[1].name`.
+    pub fn walk<F>(&self, visitor: &mut F)
+    where
+        F: FnMut(&str, &DynamicValue<'a>),
+    {
+        for (field, value) in self.fields() {
+            walk_value(field.name(), &value, visitor);
+        }
+    }
+
+    /// Copy into a DynamicMessage for editing.
+    pub fn to_message(self) -> Result<DynamicMessage<'a>, DynamicError> {
+        DynamicMessage::from_table(&self)
+    }
+
+    fn read_field(&self, field: &Field<'a>) -> Option<DynamicValue<'a>> {
+        let slot = self.table.vtable().get(field.offset()) as usize;
+        if slot == 0 {
+            return None;
+        }
+        let buf = self.table.buf();
+        let loc = self.table.loc() + slot;
+        let field_type = field.type_();
+        // Safety: The field is verified with the table.
+        unsafe {
+            match field_type.base_type() {
+                BaseType::UType => None,
+                BaseType::String => Some(DynamicValue::String(<ForwardsUOffset<&str>>::follow(
+                    buf, loc,
+                ))),
+                BaseType::Obj => read_object(&self.schema, field_type.index(), buf, loc, true),
+                BaseType::Union => {
+                    let tag = self
+                        .table
+                        .get::<u8>(field.offset() - 2, Some(0))
+                        .unwrap_or_default();
+                    let variant = union_variant(&self.schema, field, tag).ok()??;
+                    let value_loc = loc + read_scalar_at::<u32>(buf, loc) as usize;
+                    match variant.base_type() {
+                        BaseType::String => {
+                            Some(DynamicValue::String(<&str>::follow(buf, value_loc)))
+                        }
+                        BaseType::Obj => {
+                            match read_object(&self.schema, variant.index(), buf, value_loc, false)?
+                            {
+                                DynamicValue::Table(t) => Some(DynamicValue::Union(t)),
+                                other => Some(other),
+                            }
+                        }
+                        _ => None,
+                    }
+                }
+                BaseType::Vector => {
+                    let vector_loc = loc + read_scalar_at::<u32>(buf, loc) as usize;
+                    let len = read_scalar_at::<u32>(buf, vector_loc) as usize;
+                    DynamicVector::new(
+                        &self.schema,
+                        &field_type,
+                        buf,
+                        vector_loc + SIZE_UOFFSET,
+                        len,
+                    )
+                    .map(DynamicValue::Vector)
+                }
+                base_type => read_scalar(base_type, buf, loc),
+            }
+        }
+    }
+}
+
+impl<'a> DynamicStruct<'a> {
+    /// The struct descriptor from the schema.
+    pub fn descriptor(&self) -> Object<'a> {
+        self.object
+    }
+
+    /// Fully qualified type name.
+    pub fn type_name(&self) -> &'a str {
+        self.object.name()
+    }
+
+    /// Struct in its flatbuffers layout.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn get(&self, name: &str) -> Result<DynamicValue<'a>, DynamicError> {
+        let field = find_field(&self.object, name)?;
+        self.read_field(&field)
+            .ok_or_else(|| DynamicError::Unsupported(format!("{}.{name}", self.type_name())))
+    }
+
+    /// Fields in layout order.
+    pub fn fields(&self) -> impl Iterator<Item = (Field<'a>, DynamicValue<'a>)> + 'a {
+        let this = *self;
+        let mut fields = self.object.fields().iter().collect::<Vec<_>>();
+        fields.sort_by_key(|f| f.offset());
+        fields
+            .into_iter()
+            .filter_map(move |f| this.read_field(&f).map(|v| (f, v)))
+    }
+
+    fn read_field(&self, field: &Field<'a>) -> Option<DynamicValue<'a>> {
+        let loc = field.offset() as usize;
+        let field_type = field.type_();
+        match field_type.base_type() {
+            // Safety: Nested structs are inline and in range of the parent.
+            BaseType::Obj => unsafe {
+                read_object(&self.schema, field_type.index(), self.bytes, loc, true)
+            },
+            BaseType::Array => DynamicVector::new(
+                &self.schema,
+                &field_type,
+                self.bytes,
+                loc,
+                field_type.fixed_length() as usize,
+            )
+            .map(DynamicValue::Vector),
+            // Safety: The struct bytes are in range.
+            base_type => unsafe { read_scalar(base_type, self.bytes, loc) },
+        }
+    }
+}
+
+impl<'a> DynamicVector<'a> {
+    fn new(
+        schema: &Schema<'a>,
+        field_type: &Type,
+        buf: &'a [u8],
+        start: usize,
+        len: usize,
+    ) -> Option<Self> {
+        let element = field_type.element();
+        let element_size = match element {
+            BaseType::String => SIZE_UOFFSET,
+            BaseType::Obj => {
+                let object = object_at(schema, field_type.index()).ok()?;
+                if object.is_struct() {
+                    object.bytesize() as usize
+                } else {
+                    SIZE_UOFFSET
+                }
+            }
+            _ => field_type.element_size() as usize,
+        };
+        // Vectors are verified, this only guards against inconsistent schemas.
+        if start.saturating_add(len.saturating_mul(element_size)) > buf.len() {
+            return None;
+        }
+        Some(Self {
+            schema: *schema,
+            element,
+            index: field_type.index(),
+            buf,
+            start,
+            len,
+            element_size,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn element_type(&self) -> BaseType {
+        self.element
+    }
+
+    pub fn get(&self, i: usize) -> Option<DynamicValue<'a>> {
+        if i >= self.len {
+            return None;
+        }
+        let loc = self.start + i * self.element_size;
+        // Safety: The vector is verified.
+        unsafe {
+            match self.element {
+                BaseType::String => Some(DynamicValue::String(<ForwardsUOffset<&str>>::follow(
+                    self.buf, loc,
+                ))),
+                BaseType::Obj => read_object(&self.schema, self.index, self.buf, loc, true),
+                scalar => read_scalar(scalar, self.buf, loc),
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = DynamicValue<'a>> + 'a {
+        let this = *self;
+        (0..self.len).filter_map(move |i| this.get(i))
+    }
+}
+
+impl<'a> DynamicValue<'a> {
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            DynamicValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Any integer (including bool and enums) widened to i64.
+    /// u64 values that do not fit return None.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            DynamicValue::Bool(v) => Some(v as i64),
+            DynamicValue::Byte(v) => Some(v as i64),
+            DynamicValue::UByte(v) => Some(v as i64),
+            DynamicValue::Short(v) => Some(v as i64),
+            DynamicValue::UShort(v) => Some(v as i64),
+            DynamicValue::Int(v) => Some(v as i64),
+            DynamicValue::UInt(v) => Some(v as i64),
+            DynamicValue::Long(v) => Some(v),
+            DynamicValue::ULong(v) => i64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            DynamicValue::Float(v) => Some(v as f64),
+            DynamicValue::Double(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<DynamicTable<'a>> {
+        match *self {
+            DynamicValue::Table(t) | DynamicValue::Union(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<DynamicStruct<'a>> {
+        match *self {
+            DynamicValue::Struct(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_vector(&self) -> Option<DynamicVector<'a>> {
+        match *self {
+            DynamicValue::Vector(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Copy into an owned Value, with table types resolved in `schema`,
+    /// so the value does not borrow the buffer.
+    pub(crate) fn to_value_in<'s>(self, schema: &Schema<'s>) -> Result<Value<'s>, DynamicError> {
+        let value = match self {
+            DynamicValue::Bool(v) => Value::Bool(v),
+            DynamicValue::Byte(v) => Value::Byte(v),
+            DynamicValue::UByte(v) => Value::UByte(v),
+            DynamicValue::Short(v) => Value::Short(v),
+            DynamicValue::UShort(v) => Value::UShort(v),
+            DynamicValue::Int(v) => Value::Int(v),
+            DynamicValue::UInt(v) => Value::UInt(v),
+            DynamicValue::Long(v) => Value::Long(v),
+            DynamicValue::ULong(v) => Value::ULong(v),
+            DynamicValue::Float(v) => Value::Float(v),
+            DynamicValue::Double(v) => Value::Double(v),
+            DynamicValue::String(s) => Value::String(s.to_string()),
+            DynamicValue::Struct(s) => Value::Struct(s.bytes.to_vec()),
+            DynamicValue::Table(t) => Value::Table(DynamicMessage::from_table_in(schema, &t)?),
+            DynamicValue::Union(t) => {
+                Value::Union(Box::new(DynamicMessage::from_table_in(schema, &t)?))
+            }
+            DynamicValue::Vector(v) => Value::Vector(
+                v.iter()
+                    .map(|v| v.to_value_in(schema))
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        Ok(value)
+    }
+}
+
+impl std::fmt::Debug for DynamicTable<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut map = f.debug_map();
+        for (field, value) in self.fields() {
+            map.entry(&field.name(), &value);
+        }
+        map.finish()
+    }
+}
+
+impl std::fmt::Debug for DynamicStruct<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut map = f.debug_map();
+        for (field, value) in self.fields() {
+            map.entry(&field.name(), &value);
+        }
+        map.finish()
+    }
+}
+
+impl std::fmt::Debug for DynamicVector<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+fn walk_value<'a, F>(path: &str, value: &DynamicValue<'a>, visitor: &mut F)
+where
+    F: FnMut(&str, &DynamicValue<'a>),
+{
+    visitor(path, value);
+    match value {
+        DynamicValue::Table(t) | DynamicValue::Union(t) => {
+            for (field, value) in t.fields() {
+                walk_value(&format!("{path}.{}", field.name()), &value, visitor);
+            }
+        }
+        DynamicValue::Struct(s) => {
+            for (field, value) in s.fields() {
+                walk_value(&format!("{path}.{}", field.name()), &value, visitor);
+            }
+        }
+        DynamicValue::Vector(v) => {
+            for (i, value) in v.iter().enumerate() {
+                walk_value(&format!("{path}[{i}]"), &value, visitor);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Read a struct inline at loc, or a table through the offset at loc.
+/// With `indirect` false, loc is the table itself (i.e. union values).
+///
+/// # Safety
+/// The object at loc must be verified.
+unsafe fn read_object<'a>(
+    schema: &Schema<'a>,
+    index: i32,
+    buf: &'a [u8],
+    loc: usize,
+    indirect: bool,
+) -> Option<DynamicValue<'a>> {
+    let object = object_at(schema, index).ok()?;
+    if object.is_struct() {
+        let bytes = buf.get(loc..loc + object.bytesize() as usize)?;
+        Some(DynamicValue::Struct(DynamicStruct {
+            schema: *schema,
+            object,
+            bytes,
+        }))
+    } else {
+        let table = unsafe {
+            if indirect {
+                <ForwardsUOffset<Table>>::follow(buf, loc)
+            } else {
+                Table::new(buf, loc)
+            }
+        };
+        Some(DynamicValue::Table(DynamicTable {
+            schema: *schema,
+            object,
+            table,
+        }))
+    }
+}
+
+/// # Safety
+/// The scalar at loc must be verified to be of the base type.
+unsafe fn read_scalar<'a>(base_type: BaseType, buf: &[u8], loc: usize) -> Option<DynamicValue<'a>> {
+    let size = match base_type {
+        BaseType::Bool | BaseType::Byte | BaseType::UType | BaseType::UByte => 1,
+        BaseType::Short | BaseType::UShort => 2,
+        BaseType::Int | BaseType::UInt | BaseType::Float => 4,
+        BaseType::Long | BaseType::ULong | BaseType::Double => 8,
+        _ => return None,
+    };
+    if loc + size > buf.len() {
+        return None;
+    }
+    let value = unsafe {
+        match base_type {
+            BaseType::Bool => DynamicValue::Bool(read_scalar_at::<bool>(buf, loc)),
+            BaseType::Byte => DynamicValue::Byte(read_scalar_at::<i8>(buf, loc)),
+            BaseType::UType | BaseType::UByte => {
+                DynamicValue::UByte(read_scalar_at::<u8>(buf, loc))
+            }
+            BaseType::Short => DynamicValue::Short(read_scalar_at::<i16>(buf, loc)),
+            BaseType::UShort => DynamicValue::UShort(read_scalar_at::<u16>(buf, loc)),
+            BaseType::Int => DynamicValue::Int(read_scalar_at::<i32>(buf, loc)),
+            BaseType::UInt => DynamicValue::UInt(read_scalar_at::<u32>(buf, loc)),
+            BaseType::Long => DynamicValue::Long(read_scalar_at::<i64>(buf, loc)),
+            BaseType::ULong => DynamicValue::ULong(read_scalar_at::<u64>(buf, loc)),
+            BaseType::Float => DynamicValue::Float(read_scalar_at::<f32>(buf, loc)),
+            _ => DynamicValue::Double(read_scalar_at::<f64>(buf, loc)),
+        }
+    };
+    Some(value)
+}
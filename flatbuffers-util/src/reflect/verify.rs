@@ -0,0 +1,263 @@
+use flatbuffers::{
+    ForwardsUOffset, InvalidFlatbuffer, SIZE_UOFFSET, TableVerifier, UOffsetT, Vector, Verifiable,
+    Verifier, VerifierOptions,
+};
+use flatbuffers_reflection::reflection::{BaseType, Enum, Field, Object, Schema};
+
+use super::DynamicError;
+
+/// Find the object (table or struct) by its fully qualified name, i.e. `fbs.helloworld.HelloRequest`.
+pub fn find_object<'a>(schema: &Schema<'a>, name: &str) -> Result<Object<'a>, DynamicError> {
+    schema
+        .objects()
+        .lookup_by_key(name, |obj, key| obj.key_compare_with_value(key))
+        .ok_or_else(|| DynamicError::TypeNotFound(name.to_string()))
+}
+
+pub(crate) fn find_field<'a>(object: &Object<'a>, name: &str) -> Result<Field<'a>, DynamicError> {
+    object
+        .fields()
+        .lookup_by_key(name, |f, key| f.key_compare_with_value(key))
+        .ok_or_else(|| DynamicError::FieldNotFound(format!("{}.{name}", object.name())))
+}
+
+pub(crate) fn object_at<'a>(schema: &Schema<'a>, index: i32) -> Result<Object<'a>, DynamicError> {
+    let objects = schema.objects();
+    usize::try_from(index)
+        .ok()
+        .filter(|i| *i < objects.len())
+        .map(|i| objects.get(i))
+        .ok_or_else(|| DynamicError::TypeNotFound(format!("object index {index}")))
+}
+
+pub(crate) fn enum_at<'a>(schema: &Schema<'a>, index: i32) -> Result<Enum<'a>, DynamicError> {
+    let enums = schema.enums();
+    usize::try_from(index)
+        .ok()
+        .filter(|i| *i < enums.len())
+        .map(|i| enums.get(i))
+        .ok_or_else(|| DynamicError::TypeNotFound(format!("enum index {index}")))
+}
+
+/// Verify that the buffer holds a root table of type `object` at `index`.
+/// Unlike the generated `root_as_*` functions this only needs the reflection
+/// schema, and unlike the flatbuffers-reflection verifier it is not limited
+/// to the schema's `root_type`.
+pub fn verify_with_schema(
+    buf: &[u8],
+    index: usize,
+    schema: &Schema,
+    object: &Object,
+) -> Result<(), DynamicError> {
+    let opts = VerifierOptions::default();
+    let mut verifier = Verifier::new(&opts, buf);
+    let offset = verifier.get_uoffset(index)?;
+    let table_pos = index.saturating_add(offset as usize);
+    if object.is_struct() {
+        return Err(DynamicError::TypeMismatch(format!(
+            "{} is a struct and cannot be a root",
+            object.name()
+        )));
+    }
+    verify_table(&mut verifier, schema, object, table_pos)
+}
+
+fn verify_table(
+    verifier: &mut Verifier,
+    schema: &Schema,
+    object: &Object,
+    table_pos: usize,
+) -> Result<(), DynamicError> {
+    let mut table_verifier = verifier.visit_table(table_pos)?;
+    for field in object.fields() {
+        let field_type = field.type_();
+        table_verifier = match field_type.base_type() {
+            BaseType::String => visit::<ForwardsUOffset<&str>>(table_verifier, &field)?,
+            BaseType::Vector => verify_vector(table_verifier, schema, &field)?,
+            BaseType::Obj => {
+                let child = object_at(schema, field_type.index())?;
+                match table_verifier.deref(field.offset())? {
+                    Some(field_pos) if child.is_struct() => {
+                        table_verifier
+                            .verifier()
+                            .range_in_buffer(field_pos, child.bytesize() as usize)?;
+                    }
+                    Some(field_pos) => {
+                        let verifier = table_verifier.verifier();
+                        let child_pos =
+                            field_pos.saturating_add(verifier.get_uoffset(field_pos)? as usize);
+                        verify_table(verifier, schema, &child, child_pos)?;
+                    }
+                    None => missing_required(&field)?,
+                }
+                table_verifier
+            }
+            BaseType::Union => verify_union(table_verifier, schema, &field)?,
+            base_type => visit_scalar(table_verifier, &field, base_type)?,
+        };
+    }
+    table_verifier.finish();
+    Ok(())
+}
+
+fn verify_vector<'ver, 'opts, 'buf>(
+    mut table_verifier: TableVerifier<'ver, 'opts, 'buf>,
+    schema: &Schema,
+    field: &Field,
+) -> Result<TableVerifier<'ver, 'opts, 'buf>, DynamicError> {
+    let element = field.type_().element();
+    let table_verifier = match element {
+        BaseType::UType | BaseType::UByte => {
+            visit::<ForwardsUOffset<Vector<u8>>>(table_verifier, field)?
+        }
+        BaseType::Bool => visit::<ForwardsUOffset<Vector<bool>>>(table_verifier, field)?,
+        BaseType::Byte => visit::<ForwardsUOffset<Vector<i8>>>(table_verifier, field)?,
+        BaseType::Short => visit::<ForwardsUOffset<Vector<i16>>>(table_verifier, field)?,
+        BaseType::UShort => visit::<ForwardsUOffset<Vector<u16>>>(table_verifier, field)?,
+        BaseType::Int => visit::<ForwardsUOffset<Vector<i32>>>(table_verifier, field)?,
+        BaseType::UInt => visit::<ForwardsUOffset<Vector<u32>>>(table_verifier, field)?,
+        BaseType::Long => visit::<ForwardsUOffset<Vector<i64>>>(table_verifier, field)?,
+        BaseType::ULong => visit::<ForwardsUOffset<Vector<u64>>>(table_verifier, field)?,
+        BaseType::Float => visit::<ForwardsUOffset<Vector<f32>>>(table_verifier, field)?,
+        BaseType::Double => visit::<ForwardsUOffset<Vector<f64>>>(table_verifier, field)?,
+        BaseType::String => {
+            visit::<ForwardsUOffset<Vector<ForwardsUOffset<&str>>>>(table_verifier, field)?
+        }
+        BaseType::Obj => {
+            let child = object_at(schema, field.type_().index())?;
+            let Some(field_pos) = table_verifier.deref(field.offset())? else {
+                missing_required(field)?;
+                return Ok(table_verifier);
+            };
+            let verifier = table_verifier.verifier();
+            let vector_pos = field_pos.saturating_add(verifier.get_uoffset(field_pos)? as usize);
+            let len = verifier.get_uoffset(vector_pos)? as usize;
+            let start = vector_pos.saturating_add(SIZE_UOFFSET);
+            if child.is_struct() {
+                let size = child.bytesize() as usize;
+                verifier.range_in_buffer(start, len.saturating_mul(size))?;
+            } else {
+                verifier.is_aligned::<UOffsetT>(start)?;
+                verifier.range_in_buffer(start, len.saturating_mul(SIZE_UOFFSET))?;
+                for i in 0..len {
+                    let element_pos = start + i * SIZE_UOFFSET;
+                    let child_pos =
+                        element_pos.saturating_add(verifier.get_uoffset(element_pos)? as usize);
+                    verify_table(verifier, schema, &child, child_pos)?;
+                }
+            }
+            table_verifier
+        }
+        other => return Err(unsupported(field, other)),
+    };
+    Ok(table_verifier)
+}
+
+fn verify_union<'ver, 'opts, 'buf>(
+    mut table_verifier: TableVerifier<'ver, 'opts, 'buf>,
+    schema: &Schema,
+    field: &Field,
+) -> Result<TableVerifier<'ver, 'opts, 'buf>, DynamicError> {
+    // The union type field is always declared right before the union value field.
+    let type_pos = table_verifier.deref(field.offset().saturating_sub(2))?;
+    let value_pos = table_verifier.deref(field.offset())?;
+    let (type_pos, value_pos) = match (type_pos, value_pos) {
+        (Some(type_pos), Some(value_pos)) => (type_pos, value_pos),
+        (None, None) => {
+            missing_required(field)?;
+            return Ok(table_verifier);
+        }
+        _ => {
+            return Err(InvalidFlatbuffer::InconsistentUnion {
+                field: field.name().to_string().into(),
+                field_type: format!("{}_type", field.name()).into(),
+                error_trace: Default::default(),
+            }
+            .into());
+        }
+    };
+    let verifier = table_verifier.verifier();
+    let tag = verifier.get_u8(type_pos)?;
+    let Some(variant) = union_variant(schema, field, tag)? else {
+        // NONE or an unknown variant from a newer schema.
+        return Ok(table_verifier);
+    };
+    let variant_pos = value_pos.saturating_add(verifier.get_uoffset(value_pos)? as usize);
+    match variant.base_type() {
+        BaseType::String => <&str>::run_verifier(verifier, variant_pos)?,
+        BaseType::Obj => {
+            let child = object_at(schema, variant.index())?;
+            if child.is_struct() {
+                verifier.range_in_buffer(variant_pos, child.bytesize() as usize)?;
+            } else {
+                verify_table(verifier, schema, &child, variant_pos)?;
+            }
+        }
+        other => return Err(unsupported(field, other)),
+    }
+    Ok(table_verifier)
+}
+
+/// Returns the type of the union variant with the tag, None for NONE and unknown tags.
+pub(crate) fn union_variant<'a>(
+    schema: &Schema<'a>,
+    field: &Field,
+    tag: u8,
+) -> Result<Option<flatbuffers_reflection::reflection::Type<'a>>, DynamicError> {
+    if tag == 0 {
+        return Ok(None);
+    }
+    let union_enum = enum_at(schema, field.type_().index())?;
+    Ok(union_enum
+        .values()
+        .iter()
+        .find(|v| v.value() == tag as i64)
+        .and_then(|v| v.union_type()))
+}
+
+fn visit_scalar<'ver, 'opts, 'buf>(
+    table_verifier: TableVerifier<'ver, 'opts, 'buf>,
+    field: &Field,
+    base_type: BaseType,
+) -> Result<TableVerifier<'ver, 'opts, 'buf>, DynamicError> {
+    match base_type {
+        BaseType::UType | BaseType::UByte => visit::<u8>(table_verifier, field),
+        BaseType::Bool => visit::<bool>(table_verifier, field),
+        BaseType::Byte => visit::<i8>(table_verifier, field),
+        BaseType::Short => visit::<i16>(table_verifier, field),
+        BaseType::UShort => visit::<u16>(table_verifier, field),
+        BaseType::Int => visit::<i32>(table_verifier, field),
+        BaseType::UInt => visit::<u32>(table_verifier, field),
+        BaseType::Long => visit::<i64>(table_verifier, field),
+        BaseType::ULong => visit::<u64>(table_verifier, field),
+        BaseType::Float => visit::<f32>(table_verifier, field),
+        BaseType::Double => visit::<f64>(table_verifier, field),
+        other => Err(unsupported(field, other)),
+    }
+}
+
+fn visit<'ver, 'opts, 'buf, T: Verifiable>(
+    table_verifier: TableVerifier<'ver, 'opts, 'buf>,
+    field: &Field,
+) -> Result<TableVerifier<'ver, 'opts, 'buf>, DynamicError> {
+    Ok(table_verifier.visit_field::<T>(
+        field.name().to_string(),
+        field.offset(),
+        field.required(),
+    )?)
+}
+
+fn missing_required(field: &Field) -> Result<(), DynamicError> {
+    if field.required() {
+        InvalidFlatbuffer::new_missing_required(field.name().to_string())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn unsupported(field: &Field, base_type: BaseType) -> DynamicError {
+    DynamicError::Unsupported(format!(
+        "{} of type {}",
+        field.name(),
+        base_type.variant_name().unwrap_or_default()
+    ))
+}